@@ -0,0 +1,219 @@
+//! Times the core `BitArray` operations across alignments and lengths,
+//! alongside the closest equivalent workload on `bitvec`, `bit-vec` and
+//! `fixedbitset`.
+//!
+//! The other crates store their bits on the heap, so their binary operations
+//! include the clone that a fresh result costs them.
+
+use std::hint::black_box;
+
+use bitarray::bitarray::BitArray;
+use bitarray::bytes::{BitOrder, ByteOrder};
+use bitvec::order::Msb0;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fixedbitset::FixedBitSet;
+
+const LENGTHS: [u64; 3] = [8, 33, 64];
+
+// Pairs of alignments for the two operands.
+const ALIGNMENTS: [(&str, bool, bool); 3] = [
+	("left", true, true),
+	("right", false, false),
+	("mixed", true, false),
+];
+
+const SEEDS: [u64; 2] = [0x9e37_79b9_7f4a_7c15, 0xbf58_476d_1ce4_e5b9];
+
+// Takes the top `len` bits of `seed`.
+fn bit_array(len: u64, seed: u64, left_align: bool) -> BitArray<u64> {
+	let value = seed.checked_shr(64 - len as u32).unwrap_or(0);
+	BitArray::<u64>::new(value, len, left_align).unwrap()
+}
+
+fn to_bitvec(bits: &BitArray<u64>) -> bitvec::vec::BitVec<u64, Msb0> {
+	bits.iter().collect()
+}
+
+fn to_bit_vec(bits: &BitArray<u64>) -> bit_vec::BitVec {
+	bits.iter().collect()
+}
+
+fn to_fixedbitset(bits: &BitArray<u64>) -> FixedBitSet {
+	let mut set = FixedBitSet::with_capacity(bits.length() as usize);
+	for index in bits.iter_ones() {
+		set.insert(index as usize);
+	}
+	set
+}
+
+fn binary_ops(c: &mut Criterion) {
+	let mut group = c.benchmark_group("apply_binary");
+	for &len in &LENGTHS {
+		for &(name, lhs_align, rhs_align) in &ALIGNMENTS {
+			let a = bit_array(len, SEEDS[0], lhs_align);
+			let b = bit_array(len, SEEDS[1], rhs_align);
+			group.bench_with_input(
+				BenchmarkId::new(format!("bitarray/{}", name), len),
+				&(a, b),
+				|bench, &(a, b)| bench.iter(|| {
+					black_box(a).apply_binary(|x, y| x ^ y, black_box(b))
+				}),
+			);
+		}
+
+		let a = bit_array(len, SEEDS[0], true);
+		let b = bit_array(len, SEEDS[1], true);
+		let (a_bitvec, b_bitvec) = (to_bitvec(&a), to_bitvec(&b));
+		group.bench_function(BenchmarkId::new("bitvec", len), |bench| {
+			bench.iter(|| {
+				let mut result = black_box(&a_bitvec).clone();
+				result ^= black_box(b_bitvec.as_bitslice());
+				result
+			})
+		});
+
+		let (a_bit_vec, b_bit_vec) = (to_bit_vec(&a), to_bit_vec(&b));
+		group.bench_function(BenchmarkId::new("bit-vec", len), |bench| {
+			bench.iter(|| {
+				let mut result = black_box(&a_bit_vec).clone();
+				result.xor(black_box(&b_bit_vec));
+				result
+			})
+		});
+
+		let (a_set, b_set) = (to_fixedbitset(&a), to_fixedbitset(&b));
+		group.bench_function(BenchmarkId::new("fixedbitset", len), |bench| {
+			bench.iter(|| {
+				let mut result = black_box(&a_set).clone();
+				result.symmetric_difference_with(black_box(&b_set));
+				result
+			})
+		});
+	}
+	group.finish();
+}
+
+fn margins(c: &mut Criterion) {
+	let mut group = c.benchmark_group("margins");
+	for &len in &LENGTHS {
+		for &(name, lhs_align, rhs_align) in &ALIGNMENTS {
+			let a = bit_array(len, SEEDS[0], lhs_align);
+			let b = bit_array(len / 2, SEEDS[1], rhs_align);
+			group.bench_with_input(
+				BenchmarkId::new(format!("aligned_to/{}", name), len),
+				&(a, b),
+				|bench, &(a, b)| {
+					bench.iter(|| black_box(a).aligned_to(black_box(b)))
+				},
+			);
+			group.bench_with_input(
+				BenchmarkId::new(format!("trim_to/{}", name), len),
+				&a,
+				|bench, &a| {
+					bench.iter(|| black_box(a).trim_to(black_box(len / 2)))
+				},
+			);
+		}
+	}
+	group.finish();
+}
+
+fn popcount(c: &mut Criterion) {
+	let mut group = c.benchmark_group("count_ones");
+	for &len in &LENGTHS {
+		for &left_align in &[true, false] {
+			let bits = bit_array(len, SEEDS[0], left_align);
+			let name = if left_align {"bitarray/left"} else {"bitarray/right"};
+			group.bench_function(BenchmarkId::new(name, len), |bench| {
+				bench.iter(|| black_box(bits).count_ones())
+			});
+		}
+
+		let a = bit_array(len, SEEDS[0], true);
+		let bits = to_bitvec(&a);
+		group.bench_function(BenchmarkId::new("bitvec", len), |bench| {
+			bench.iter(|| black_box(&bits).count_ones())
+		});
+		let bits = to_bit_vec(&a);
+		group.bench_function(BenchmarkId::new("bit-vec", len), |bench| {
+			bench.iter(|| black_box(&bits).count_ones())
+		});
+		let set = to_fixedbitset(&a);
+		group.bench_function(BenchmarkId::new("fixedbitset", len), |bench| {
+			bench.iter(|| black_box(&set).count_ones(..))
+		});
+	}
+	group.finish();
+}
+
+fn iteration(c: &mut Criterion) {
+	let mut group = c.benchmark_group("iter_ones");
+	for &len in &LENGTHS {
+		for &left_align in &[true, false] {
+			let bits = bit_array(len, SEEDS[0], left_align);
+			let name = if left_align {"bitarray/left"} else {"bitarray/right"};
+			group.bench_function(BenchmarkId::new(name, len), |bench| {
+				bench.iter(|| black_box(bits).iter_ones().sum::<u64>())
+			});
+		}
+
+		let a = bit_array(len, SEEDS[0], true);
+		let bits = to_bitvec(&a);
+		group.bench_function(BenchmarkId::new("bitvec", len), |bench| {
+			bench.iter(|| black_box(&bits).iter_ones().sum::<usize>())
+		});
+		let bits = to_bit_vec(&a);
+		group.bench_function(BenchmarkId::new("bit-vec", len), |bench| {
+			bench.iter(|| {
+				black_box(&bits).iter().enumerate()
+					.filter(|&(_, bit)| bit)
+					.map(|(index, _)| index)
+					.sum::<usize>()
+			})
+		});
+		let set = to_fixedbitset(&a);
+		group.bench_function(BenchmarkId::new("fixedbitset", len), |bench| {
+			bench.iter(|| black_box(&set).ones().sum::<usize>())
+		});
+	}
+	group.finish();
+}
+
+fn serialization(c: &mut Criterion) {
+	let mut group = c.benchmark_group("serialization");
+	for &len in &LENGTHS {
+		let a = bit_array(len, SEEDS[0], true);
+		let bytes = a.to_bytes(ByteOrder::Big, BitOrder::Msb0);
+		let literal = a.to_string();
+		let id = |name: &str| BenchmarkId::new(name, len);
+
+		group.bench_function(id("bitarray/to_bytes"), |bench| {
+			bench.iter(|| black_box(a).to_bytes(ByteOrder::Big, BitOrder::Msb0))
+		});
+		group.bench_function(id("bitarray/from_bytes"), |bench| {
+			bench.iter(|| BitArray::<u64>::from_bytes(
+				black_box(&bytes), len, ByteOrder::Big, BitOrder::Msb0, true,
+			))
+		});
+		group.bench_function(id("bitarray/to_string"), |bench| {
+			bench.iter(|| black_box(a).to_string())
+		});
+		group.bench_function(id("bitarray/parse"), |bench| {
+			bench.iter(|| black_box(&literal).parse::<BitArray<u64>>())
+		});
+
+		let bits = to_bit_vec(&a);
+		group.bench_function(id("bit-vec/to_bytes"), |bench| {
+			bench.iter(|| black_box(&bits).to_bytes())
+		});
+		group.bench_function(id("bit-vec/from_bytes"), |bench| {
+			bench.iter(|| bit_vec::BitVec::from_bytes(black_box(&bytes)))
+		});
+	}
+	group.finish();
+}
+
+criterion_group!(
+	benches, binary_ops, margins, popcount, iteration, serialization,
+);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+#![no_main]
+
+use bitarray::bitarray::BitArray;
+use libfuzzer_sys::fuzz_target;
+
+// Builds an array from a word and two margin bytes, clearing whatever the
+// margins leave outside of the window.
+fn bit_array(word: u64, left: u8, right: u8, left_align: bool)
+	-> BitArray<u64>
+{
+	let left_margin = left as u64 % 65;
+	let right_margin = right as u64 % (65 - left_margin);
+	let mask = BitArray::<u64>::from_margins(
+		0, left_margin, right_margin, left_align,
+	).unwrap().mask();
+	BitArray::<u64>::from_margins(
+		word & mask, left_margin, right_margin, left_align,
+	).unwrap()
+}
+
+fuzz_target!(|input: (u64, u8, u8, bool, u64, u8, u8, bool, u8)| {
+	let (word, left, right, left_align, ..) = input;
+	let a = bit_array(word, left, right, left_align);
+	let (.., word, left, right, left_align, op) = input;
+	let b = bit_array(word, left, right, left_align);
+
+	let result = match op % 3 {
+		0 => a.apply_binary(|x, y| x & y, b),
+		1 => a.apply_binary(|x, y| x | y, b),
+		_ => a.apply_binary(|x, y| x ^ y, b),
+	};
+	assert_eq!(result.length(), u64::min(a.length(), b.length()));
+	assert_eq!(result.is_left_aligned(), a.is_left_aligned());
+	assert_eq!(
+		BitArray::<u64>::from_margins(
+			u64::from(result) << result.right_margin(),
+			result.left_margin(),
+			result.right_margin(),
+			result.is_left_aligned(),
+		),
+		Ok(result),
+	);
+});
@@ -0,0 +1,33 @@
+#![no_main]
+
+use bitarray::bitarray::BitArray;
+use bitarray::bytes::{BitOrder, ByteOrder};
+use bitarray::io::SliceReader;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+	if let Ok(text) = std::str::from_utf8(data) {
+		if let Ok(bits) = text.parse::<BitArray<u64>>() {
+			let suffix = if bits.is_left_aligned() {":L"} else {":R"};
+			let literal = format!("{}{}", bits, suffix);
+			assert_eq!(literal.parse::<BitArray<u64>>(), Ok(bits));
+		}
+	}
+
+	if let Some((&len, bytes)) = data.split_first() {
+		let len = len as u64 % 65;
+		if let Ok(bits) = BitArray::<u64>::from_bytes(
+			bytes, len, ByteOrder::Big, BitOrder::Lsb0, true,
+		) {
+			assert_eq!(*bits.to_bytes(ByteOrder::Big, BitOrder::Lsb0), *bytes);
+		}
+
+		let mut reader = SliceReader::new(bytes, BitOrder::Msb0);
+		while let Ok(bits) = reader.read_bits::<u64>(len) {
+			assert_eq!(bits.length(), len);
+			if len == 0 {
+				break;
+			}
+		}
+	}
+});
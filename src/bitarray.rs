@@ -1,86 +1,854 @@
-use std::convert::{From, Into};
+use core::cmp::Ordering;
+use core::convert::From;
+use core::error::Error;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{
+	BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Bound,
+	Not, Range, RangeBounds, Shl, ShlAssign, Shr, ShrAssign,
+};
 
+use crate::bitstore::BitStore;
 
-#[derive(Debug, Clone, Copy)]
-struct BitArray {
-	array: u64,
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitArrayError {
+	/// The requested length does not fit in a single storage word.
+	LengthOverflow { length: u64, capacity: u64 },
+	/// The margins leave fewer than zero bits for the window.
+	MarginOverflow { left_margin: u64, right_margin: u64, capacity: u64 },
+	/// The backing word has set bits outside of the window.
+	BitsOutsideWindow { array: u128, mask: u128 },
+	/// The number of bytes given does not match the length of the window.
+	ByteCount { count: usize, expected: usize },
+}
+
+impl fmt::Display for BitArrayError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			BitArrayError::LengthOverflow { length, capacity } =>
+				write!(f, "length {} exceeds the {}-bit capacity",
+					length, capacity),
+			BitArrayError::MarginOverflow {
+				left_margin, right_margin, capacity
+			} =>
+				write!(f, "margins {} + {} exceed the {}-bit capacity",
+					left_margin, right_margin, capacity),
+			BitArrayError::BitsOutsideWindow { array, mask } =>
+				write!(f, "bits {:#x} lie outside of the window {:#x}",
+					array & !mask, mask),
+			BitArrayError::ByteCount { count, expected } =>
+				write!(f, "expected {} bytes, found {}", expected, count),
+		}
+	}
+}
+
+impl Error for BitArrayError {}
+
+
+/// The result of joining two arrays would not fit in a single storage word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError {
+	pub length: u64,
+	pub capacity: u64,
+}
+
+impl fmt::Display for CapacityError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "combined length {} exceeds the {}-bit capacity",
+			self.length, self.capacity)
+	}
+}
+
+impl Error for CapacityError {}
+
+impl From<CapacityError> for BitArrayError {
+	fn from(err: CapacityError) -> BitArrayError {
+		BitArrayError::LengthOverflow {
+			length: err.length,
+			capacity: err.capacity,
+		}
+	}
+}
+
+
+/// A window of up to `W::BITS` bits held in a single word.
+///
+/// The margins always sum to at most `W::BITS` and the bits outside of the
+/// window are always cleared; every constructor checks both, and every safe
+/// method keeps them so. The `unsafe` `*_unchecked` accessors rely on their
+/// callers passing indices below `length()`. The window may be empty or
+/// cover the whole word.
+#[derive(Clone, Copy)]
+pub struct BitArray<W: BitStore = u64> {
+	array: W,
 	left_margin: u64,
 	right_margin: u64,
 	left_align: bool,
 }
 
-impl PartialEq for BitArray {
+impl<W: BitStore> PartialEq for BitArray<W> {
 	fn eq(&self, other: &Self) -> bool {
 		self.left_align == other.left_align
-		&& u64::from(*self) == u64::from(*other)
+		&& W::value(self) == W::value(other)
 		&& self.length() == other.length()
 	}
 }
 
-impl From<BitArray> for u64 {
-	fn from(ba: BitArray) -> u64 {
-		(ba.array & (!0u64 >> ba.left_margin)) >> ba.right_margin
+impl<W: BitStore> Eq for BitArray<W> {}
+
+/// Hashes the same parts that `==` compares: the alignment, the length and
+/// the value, but not the margins.
+impl<W: BitStore> Hash for BitArray<W> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.left_align.hash(state);
+		self.length().hash(state);
+		W::value(self).hash(state);
 	}
 }
 
-impl BitArray {
-	pub fn length(&self) -> u64 {
-		64u64 - (self.left_margin + self.right_margin)
+/// Orders arrays as bit strings read from index 0, so a prefix sorts before
+/// any longer array that starts with it. Arrays holding the same bits sort
+/// right-aligned first.
+///
+/// See `numeric_cmp` to order arrays by value instead.
+impl<W: BitStore> Ord for BitArray<W> {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.index_key().cmp(&other.index_key())
+			.then(self.length().cmp(&other.length()))
+			.then(self.left_align.cmp(&other.left_align))
 	}
+}
 
-	fn mask(&self) -> u64 {
-		(!0u64 >> self.left_margin) & (!0u64 << self.right_margin)
+impl<W: BitStore> PartialOrd for BitArray<W> {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
 	}
+}
+
+// `const fn` cannot call trait methods, so everything that operates on the
+// word itself is written out for each word type. Generic code reaches these
+// through the matching `BitStore` hooks.
+macro_rules! impl_const_api {
+	($($W:ty),*) => {$(
+		impl BitArray<$W> {
+			/// Creates a `len`-bit array holding `value`, flush against the
+			/// aligned side of the word.
+			pub const fn new(value: $W, len: u64, left_align: bool)
+				-> Result<Self, BitArrayError>
+			{
+				const BITS: u64 = <$W as BitStore>::BITS;
+				if len > BITS {
+					return Err(BitArrayError::LengthOverflow {
+						length: len,
+						capacity: BITS,
+					});
+				}
+				if Self::shr(value, len) != 0 {
+					return Err(BitArrayError::BitsOutsideWindow {
+						array: value as u128,
+						mask: !Self::shl(<$W>::MAX, len) as u128,
+					});
+				}
 
-	fn aligned_to(self, bits: Self) -> Self {
-		if bits.left_align {
-			Self {
-				array: (self.array << self.left_margin) >> bits.left_margin,
-				left_margin: bits.left_margin,
-				right_margin: 64-u64::max(self.length(), bits.length()),
-				left_align: self.left_align
+				let (left_margin, right_margin) =
+					if left_align {(0, BITS-len)}
+					else {(BITS-len, 0)};
+				Self::from_margins(
+					Self::shl(value, right_margin),
+					left_margin,
+					right_margin,
+					left_align,
+				)
 			}
-		} else {
-			Self {
-				array: (self.array >> self.right_margin) << bits.right_margin,
-				left_margin: 64-u64::max(self.length(), bits.length()),
-				right_margin: bits.right_margin,
-				left_align: self.left_align
+
+			/// Creates a `len`-bit array with every bit cleared.
+			pub const fn zeros(len: u64, left_align: bool)
+				-> Result<Self, BitArrayError>
+			{
+				Self::new(0, len, left_align)
+			}
+
+			/// Creates a `len`-bit array with every bit set.
+			pub const fn ones(len: u64, left_align: bool)
+				-> Result<Self, BitArrayError>
+			{
+				match Self::zeros(len, left_align) {
+					Ok(mut bits) => {
+						bits.array = bits.mask();
+						Ok(bits)
+					},
+					Err(err) => Err(err),
+				}
+			}
+
+			/// Wraps a raw word, using the margins to select the window of
+			/// significant bits.
+			pub const fn from_margins(
+				array: $W,
+				left_margin: u64,
+				right_margin: u64,
+				left_align: bool,
+			) -> Result<Self, BitArrayError> {
+				const BITS: u64 = <$W as BitStore>::BITS;
+				if left_margin.saturating_add(right_margin) > BITS {
+					return Err(BitArrayError::MarginOverflow {
+						left_margin,
+						right_margin,
+						capacity: BITS,
+					});
+				}
+
+				let bits = Self {array, left_margin, right_margin, left_align};
+				if array & !bits.mask() != 0 {
+					return Err(BitArrayError::BitsOutsideWindow {
+						array: array as u128,
+						mask: bits.mask() as u128,
+					});
+				}
+				Ok(bits)
+			}
+
+			/// Returns the window shifted down to the least significant bits.
+			pub const fn value(&self) -> $W {
+				Self::shr(
+					self.array & Self::shr(<$W>::MAX, self.left_margin),
+					self.right_margin,
+				)
 			}
+
+			/// Selects the window within the word.
+			pub const fn mask(&self) -> $W {
+				Self::shr(<$W>::MAX, self.left_margin)
+					& Self::shl(<$W>::MAX, self.right_margin)
+			}
+
+			/// Keeps the first `new_len` bits from the aligned side, clearing
+			/// the rest.
+			pub const fn trim_to(self, new_len: u64) -> Self {
+				const BITS: u64 = <$W as BitStore>::BITS;
+				if new_len >= self.length() {
+					return self;
+				}
+
+				let mut bits = Self {
+					left_margin:
+						if self.left_align {self.left_margin}
+						else {BITS - self.right_margin - new_len},
+					right_margin:
+						if !self.left_align {self.right_margin}
+						else {BITS - self.left_margin - new_len},
+					..self
+				};
+				bits.array &= bits.mask();
+				bits
+			}
+
+			pub const fn aligned_to(self, bits: Self) -> Self {
+				const BITS: u64 = <$W as BitStore>::BITS;
+				if bits.left_align {
+					Self {
+						array: Self::shr(
+							Self::shl(self.array, self.left_margin),
+							bits.left_margin,
+						),
+						left_margin: bits.left_margin,
+						right_margin: BITS.saturating_sub(
+							bits.left_margin + self.length()
+						),
+						left_align: self.left_align
+					}
+				} else {
+					Self {
+						array: Self::shl(
+							Self::shr(self.array, self.right_margin),
+							bits.right_margin,
+						),
+						left_margin: BITS.saturating_sub(
+							bits.right_margin + self.length()
+						),
+						right_margin: bits.right_margin,
+						left_align: self.left_align
+					}
+				}
+			}
+
+			/// The `const` counterpart of `&`.
+			pub const fn and(self, bits: Self) -> Self {
+				let (mut window, lhs, rhs) = self.overlap(bits);
+				window.array = lhs & rhs & window.mask();
+				window
+			}
+
+			/// The `const` counterpart of `|`.
+			pub const fn or(self, bits: Self) -> Self {
+				let (mut window, lhs, rhs) = self.overlap(bits);
+				window.array = (lhs | rhs) & window.mask();
+				window
+			}
+
+			/// The `const` counterpart of `^`.
+			pub const fn xor(self, bits: Self) -> Self {
+				let (mut window, lhs, rhs) = self.overlap(bits);
+				window.array = (lhs ^ rhs) & window.mask();
+				window
+			}
+
+			/// The `const` counterpart of `!`.
+			pub const fn complement(self) -> Self {
+				Self {
+					array: !self.array & self.mask(),
+					..self
+				}
+			}
+
+			// Lines `bits` up against `self`, returning their shared window
+			// along with the words to combine within it.
+			pub(crate) const fn overlap(self, bits: Self) -> (Self, $W, $W) {
+				let bits = bits.aligned_to(self);
+				let self_ = self.trim_to(bits.length());
+
+				let window = Self {
+					array: 0,
+					left_margin:
+						if self_.left_margin > bits.left_margin {self_.left_margin}
+						else {bits.left_margin},
+					right_margin:
+						if self_.right_margin > bits.right_margin {self_.right_margin}
+						else {bits.right_margin},
+					left_align: self_.left_align,
+				};
+				(window, self_.array, bits.array)
+			}
+
+			// Shifts that clear the word, rather than overflow, when a
+			// margin spans all of it.
+			const fn shl(word: $W, n: u64) -> $W {
+				if n < <$W as BitStore>::BITS {word << n} else {0}
+			}
+
+			const fn shr(word: $W, n: u64) -> $W {
+				if n < <$W as BitStore>::BITS {word >> n} else {0}
+			}
+		}
+
+		impl From<BitArray<$W>> for $W {
+			fn from(ba: BitArray<$W>) -> $W {
+				ba.value()
+			}
+		}
+	)*};
+}
+
+impl_const_api!(u8, u16, u32, u64, u128, usize);
+
+// `u128` words are left out, since their values need not fit in a `u64`.
+macro_rules! impl_to_u64 {
+	($($W:ty),*) => {$(
+		impl BitArray<$W> {
+			/// Returns `value()` widened to a `u64`; usable in `const` contexts,
+			/// unlike `u64::from`.
+			pub const fn to_u64(&self) -> u64 {
+				self.value() as u64
+			}
+		}
+	)*};
+}
+
+impl_to_u64!(u8, u16, u32, u64, usize);
+
+impl<W: BitStore> BitArray<W> {
+	pub const fn length(&self) -> u64 {
+		W::BITS - (self.left_margin + self.right_margin)
+	}
+
+	pub const fn is_left_aligned(&self) -> bool {
+		self.left_align
+	}
+
+	/// The number of unused bits above the window.
+	pub const fn left_margin(&self) -> u64 {
+		self.left_margin
+	}
+
+	/// The number of unused bits below the window.
+	pub const fn right_margin(&self) -> u64 {
+		self.right_margin
+	}
+
+	/// Returns the bit at `index`, counting from the aligned side, or `None`
+	/// if the index lies outside of the window.
+	pub fn get(&self, index: u64) -> Option<bool> {
+		if index < self.length() {
+			Some(self.read_bit(index))
+		} else {
+			None
+		}
+	}
+
+	/// Sets the bit at `index`, counting from the aligned side.
+	///
+	/// Panics if the index lies outside of the window.
+	pub fn set(&mut self, index: u64, value: bool) {
+		self.check_index(index);
+		self.write_bit(index, value);
+	}
+
+	/// Flips the bit at `index`, counting from the aligned side.
+	///
+	/// Panics if the index lies outside of the window.
+	pub fn toggle(&mut self, index: u64) {
+		self.check_index(index);
+		self.array = self.array ^ self.bit(index);
+	}
+
+	/// Sets the bit at `index` and returns its previous value.
+	///
+	/// Panics if the index lies outside of the window.
+	pub fn replace(&mut self, index: u64, value: bool) -> bool {
+		self.check_index(index);
+		let old = self.read_bit(index);
+		self.write_bit(index, value);
+		old
+	}
+
+	/// Like `get`, but without checking `index` against `length()`.
+	///
+	/// # Safety
+	///
+	/// `index` must be less than `length()`.
+	pub unsafe fn get_unchecked(&self, index: u64) -> bool {
+		debug_assert!(index < self.length());
+		self.read_bit(index)
+	}
+
+	/// Like `set`, but without checking `index` against `length()`.
+	///
+	/// # Safety
+	///
+	/// `index` must be less than `length()`; any other index would write
+	/// into the margins, which must stay clear.
+	pub unsafe fn set_unchecked(&mut self, index: u64, value: bool) {
+		debug_assert!(index < self.length());
+		self.write_bit(index, value);
+	}
+
+	/// Like `toggle`, but without checking `index` against `length()`.
+	///
+	/// # Safety
+	///
+	/// `index` must be less than `length()`; any other index would write
+	/// into the margins, which must stay clear.
+	pub unsafe fn toggle_unchecked(&mut self, index: u64) {
+		debug_assert!(index < self.length());
+		self.array = self.array ^ self.bit(index);
+	}
+
+	/// Like `replace`, but without checking `index` against `length()`.
+	///
+	/// # Safety
+	///
+	/// `index` must be less than `length()`; any other index would write
+	/// into the margins, which must stay clear.
+	pub unsafe fn replace_unchecked(&mut self, index: u64, value: bool) -> bool {
+		debug_assert!(index < self.length());
+		let old = self.read_bit(index);
+		self.write_bit(index, value);
+		old
+	}
+
+	fn read_bit(&self, index: u64) -> bool {
+		self.array & self.bit(index) != W::ZERO
+	}
+
+	fn write_bit(&mut self, index: u64, value: bool) {
+		self.array =
+			if value {self.array | self.bit(index)}
+			else {self.array & !self.bit(index)};
+	}
+
+	fn check_index(&self, index: u64) {
+		assert!(index < self.length(), "index {} out of range for length {}",
+			index, self.length());
+	}
+
+	// Selects the bit at `index`, counting from the aligned side; `index`
+	// must lie within the window.
+	fn bit(&self, index: u64) -> W {
+		let position =
+			if self.left_align {W::BITS - self.left_margin - 1 - index}
+			else {self.right_margin + index};
+		W::ONE << position
+	}
+
+	/// Selects the bits in `range`, counting from the aligned side.
+	///
+	/// Only the margins move, so the selected bits keep their place in the
+	/// word. Panics if the range does not lie within the window.
+	pub fn slice<R: RangeBounds<u64>>(&self, range: R) -> Self {
+		let (start, end) = check_range(range, self.length());
+		let (before, after) = (start, self.length() - end);
+
+		let mut bits = Self {
+			left_margin: self.left_margin
+				+ if self.left_align {before} else {after},
+			right_margin: self.right_margin
+				+ if self.left_align {after} else {before},
+			..*self
+		};
+		bits.array = bits.array & W::mask(&bits);
+		bits
+	}
+
+	/// Reads the `width`-bit field starting `offset` bits from the aligned
+	/// side.
+	///
+	/// Panics if the field does not lie within the window.
+	pub fn extract_field(&self, offset: u64, width: u64) -> W {
+		W::value(&self.slice(field_range(offset, width)))
+	}
+
+	/// Overwrites the `width`-bit field starting `offset` bits from the
+	/// aligned side.
+	///
+	/// Panics if the field does not lie within the window or `value` does
+	/// not fit in `width` bits.
+	pub fn insert_field(&mut self, offset: u64, width: u64, value: W) {
+		let field = self.slice(field_range(offset, width));
+		assert!(width >= W::BITS || value >> width == W::ZERO,
+			"value {:#b} does not fit in {} bits", value, width);
+		if width == 0 {
+			return;
+		}
+
+		self.array = (self.array & !W::mask(&field))
+			| (value << field.right_margin);
+	}
+
+	/// Joins `other` onto the non-aligned end of `self`.
+	///
+	/// The result keeps the alignment of `self`, and each operand keeps its
+	/// own index order: index `i` of `other` lands at index
+	/// `self.length() + i`, whichever side `other` is aligned to.
+	pub fn concat(self, other: Self) -> Result<Self, CapacityError> {
+		let len = self.length() + other.length();
+		if len > W::BITS {
+			return Err(CapacityError {length: len, capacity: W::BITS});
 		}
+		let other =
+			if other.left_align == self.left_align {other}
+			else {other.realigned()};
+
+		// Leave `self` in place when there is room for `other` beside it.
+		let (left_margin, right_margin) = if self.left_align {
+			let left_margin = u64::min(self.left_margin, W::BITS - len);
+			(left_margin, W::BITS - len - left_margin)
+		} else {
+			let right_margin = u64::min(self.right_margin, W::BITS - len);
+			(W::BITS - len - right_margin, right_margin)
+		};
+		let target = |left_align| Self {
+			array: W::ZERO,
+			left_margin,
+			right_margin,
+			left_align,
+		};
+
+		let head = W::aligned_to(self, target(self.left_align));
+		let tail = W::aligned_to(other, target(!self.left_align));
+		Ok(Self {
+			array: (head.array & W::mask(&head)) | (tail.array & W::mask(&tail)),
+			..target(self.left_align)
+		})
 	}
 
-	fn trim_to(self, new_len: u64) -> BitArray {
-		if new_len >= self.length() {
+	// Switches to the other alignment, reversing the value so that every
+	// index keeps its bit.
+	fn realigned(self) -> Self {
+		let len = self.length();
+		let value =
+			if len == 0 {0}
+			else {W::value(&self).to_u128().reverse_bits() >> (128 - len)};
+		W::new(W::from_u128(value), len, !self.left_align)
+			.expect("the reversed value fits the same window")
+	}
+
+	/// Splits into the first `index` bits from the aligned side and the rest.
+	///
+	/// Panics if `index` is greater than `length()`.
+	pub fn split_at(&self, index: u64) -> (Self, Self) {
+		(self.slice(..index), self.slice(index..))
+	}
+
+	/// Rotates the window `n` bits towards its most significant end.
+	pub fn rotate_left(self, n: u64) -> Self {
+		if self.length() == 0 || n.is_multiple_of(self.length()) {
 			return self;
 		}
-		
+		let n = n % self.length();
+		let array = self.array & W::mask(&self);
 		Self {
-			array: self.array,
-			left_margin: 
-				if self.left_align {self.left_margin}
-				else {64-self.right_margin-new_len},
-			right_margin:
-				if !self.left_align {self.right_margin}
-				else {64-self.left_margin-new_len},
-			left_align: self.left_align,
+			array: ((array << n) | (array >> (self.length() - n)))
+				& W::mask(&self),
+			..self
+		}
+	}
+
+	/// Rotates the window `n` bits towards its least significant end.
+	pub fn rotate_right(self, n: u64) -> Self {
+		if self.length() == 0 {
+			return self;
 		}
+		self.rotate_left(self.length() - n % self.length())
+	}
+
+	pub fn count_ones(&self) -> u64 {
+		(self.array & W::mask(self)).count_ones()
+	}
+
+	pub fn count_zeros(&self) -> u64 {
+		self.length() - self.count_ones()
 	}
 
-	fn apply_binary<F>(&self, func: F, bits: Self) -> Self
-		where F: Fn(u64, u64) -> u64
+	/// Counts the cleared bits from the aligned side up to the first set bit.
+	pub fn leading_zeros(&self) -> u64 {
+		if self.left_align {self.high_zeros()} else {self.low_zeros()}
+	}
+
+	/// Counts the cleared bits from the non-aligned end back to the last set
+	/// bit.
+	pub fn trailing_zeros(&self) -> u64 {
+		if self.left_align {self.low_zeros()} else {self.high_zeros()}
+	}
+
+	/// Counts the set bits from the aligned side up to the first cleared bit.
+	pub fn leading_ones(&self) -> u64 {
+		(!*self).leading_zeros()
+	}
+
+	/// Counts the set bits from the non-aligned end back to the last cleared
+	/// bit.
+	pub fn trailing_ones(&self) -> u64 {
+		(!*self).trailing_zeros()
+	}
+
+	/// Returns `true` when an odd number of bits are set.
+	pub fn parity(&self) -> bool {
+		self.count_ones() % 2 == 1
+	}
+
+	pub fn any(&self) -> bool {
+		self.array & W::mask(self) != W::ZERO
+	}
+
+	pub fn all(&self) -> bool {
+		self.array & W::mask(self) == W::mask(self)
+	}
+
+	pub fn none(&self) -> bool {
+		!self.any()
+	}
+
+	/// Orders arrays by value alone, so arrays of different lengths or
+	/// alignments can compare equal.
+	pub fn numeric_cmp(&self, other: &Self) -> Ordering {
+		W::value(self).to_u128().cmp(&W::value(other).to_u128())
+	}
+
+	// Lays the bits out from the most significant end of a `u128` in index
+	// order, so that comparing keys compares the arrays as bit strings.
+	fn index_key(&self) -> u128 {
+		let value = W::value(self).to_u128();
+		if !self.left_align {
+			value.reverse_bits()
+		} else if self.length() == 0 {
+			0
+		} else {
+			value << (128 - self.length())
+		}
+	}
+
+	// Counts the cleared bits at the most significant end of the window.
+	fn high_zeros(&self) -> u64 {
+		let window = self.array & W::mask(self);
+		if window == W::ZERO {
+			return self.length();
+		}
+		window.leading_zeros() - self.left_margin
+	}
+
+	// Counts the cleared bits at the least significant end of the window.
+	fn low_zeros(&self) -> u64 {
+		let window = self.array & W::mask(self);
+		if window == W::ZERO {
+			return self.length();
+		}
+		window.trailing_zeros() - self.right_margin
+	}
+
+	pub fn apply_binary<F>(&self, func: F, bits: Self) -> Self
+		where F: Fn(W, W) -> W
 	{
-		let bits = bits.aligned_to(*self);
-		let self_ = self.trim_to(bits.length());
+		let (mut window, lhs, rhs) = W::overlap(*self, bits);
+		window.array = func(lhs, rhs) & W::mask(&window);
+		window
+	}
+
+}
+
+
+// Resolves `range` against a length, panicking if it does not fit.
+// The bits of a field, saturating so that a field past `u64::MAX` fails the
+// usual range check instead of overflowing.
+fn field_range(offset: u64, width: u64) -> Range<u64> {
+	offset..offset.saturating_add(width)
+}
+
+pub(crate) fn check_range<R: RangeBounds<u64>>(range: R, len: u64)
+	-> (u64, u64)
+{
+	let start = match range.start_bound() {
+		Bound::Included(&start) => start,
+		Bound::Excluded(&start) => start + 1,
+		Bound::Unbounded => 0,
+	};
+	let end = match range.end_bound() {
+		Bound::Included(&end) => end + 1,
+		Bound::Excluded(&end) => end,
+		Bound::Unbounded => len,
+	};
+	assert!(start <= end && end <= len,
+		"range {}..{} out of range for length {}", start, end, len);
+	(start, end)
+}
+
+macro_rules! impl_binary_op {
+	($Op:ident, $op:ident, $OpAssign:ident, $op_assign:ident, $func:expr) => {
+		impl<W: BitStore> $Op for BitArray<W> {
+			type Output = BitArray<W>;
+
+			fn $op(self, bits: BitArray<W>) -> BitArray<W> {
+				self.apply_binary($func, bits)
+			}
+		}
+
+		impl<W: BitStore> $Op<&BitArray<W>> for BitArray<W> {
+			type Output = BitArray<W>;
+
+			fn $op(self, bits: &BitArray<W>) -> BitArray<W> {
+				self.apply_binary($func, *bits)
+			}
+		}
+
+		impl<W: BitStore> $Op<BitArray<W>> for &BitArray<W> {
+			type Output = BitArray<W>;
+
+			fn $op(self, bits: BitArray<W>) -> BitArray<W> {
+				self.apply_binary($func, bits)
+			}
+		}
+
+		impl<W: BitStore> $Op<&BitArray<W>> for &BitArray<W> {
+			type Output = BitArray<W>;
+
+			fn $op(self, bits: &BitArray<W>) -> BitArray<W> {
+				self.apply_binary($func, *bits)
+			}
+		}
+
+		impl<W: BitStore> $OpAssign for BitArray<W> {
+			fn $op_assign(&mut self, bits: BitArray<W>) {
+				*self = self.apply_binary($func, bits);
+			}
+		}
+
+		impl<W: BitStore> $OpAssign<&BitArray<W>> for BitArray<W> {
+			fn $op_assign(&mut self, bits: &BitArray<W>) {
+				*self = self.apply_binary($func, *bits);
+			}
+		}
+	};
+}
+
+impl_binary_op!(BitAnd, bitand, BitAndAssign, bitand_assign, |x, y| x & y);
+impl_binary_op!(BitOr, bitor, BitOrAssign, bitor_assign, |x, y| x | y);
+impl_binary_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, |x, y| x ^ y);
+
+impl<W: BitStore> Not for BitArray<W> {
+	type Output = BitArray<W>;
+
+	fn not(self) -> BitArray<W> {
+		Self {
+			array: !self.array & W::mask(&self),
+			..self
+		}
+	}
+}
+
+impl<W: BitStore> Not for &BitArray<W> {
+	type Output = BitArray<W>;
+
+	fn not(self) -> BitArray<W> {
+		!*self
+	}
+}
+
+/// Shifts the window `n` bits towards its most significant end, dropping the
+/// bits that leave the window and filling with zeros.
+impl<W: BitStore> Shl<u64> for BitArray<W> {
+	type Output = BitArray<W>;
+
+	fn shl(self, n: u64) -> BitArray<W> {
+		Self {
+			array:
+				if n >= self.length() {W::ZERO}
+				else {((self.array & W::mask(&self)) << n) & W::mask(&self)},
+			..self
+		}
+	}
+}
+
+/// Shifts the window `n` bits towards its least significant end, dropping
+/// the bits that leave the window and filling with zeros.
+impl<W: BitStore> Shr<u64> for BitArray<W> {
+	type Output = BitArray<W>;
 
+	fn shr(self, n: u64) -> BitArray<W> {
 		Self {
-			array: func(self_.array, bits.array),
-			left_margin: u64::max(self_.left_margin, bits.left_margin),
-			right_margin: u64::max(self_.right_margin, bits.right_margin),
-			left_align: self_.left_align,
+			array:
+				if n >= self.length() {W::ZERO}
+				else {((self.array & W::mask(&self)) >> n) & W::mask(&self)},
+			..self
 		}
 	}
+}
 
+impl<W: BitStore> Shl<u64> for &BitArray<W> {
+	type Output = BitArray<W>;
+
+	fn shl(self, n: u64) -> BitArray<W> {
+		*self << n
+	}
+}
+
+impl<W: BitStore> Shr<u64> for &BitArray<W> {
+	type Output = BitArray<W>;
+
+	fn shr(self, n: u64) -> BitArray<W> {
+		*self >> n
+	}
+}
+
+impl<W: BitStore> ShlAssign<u64> for BitArray<W> {
+	fn shl_assign(&mut self, n: u64) {
+		*self = *self << n;
+	}
+}
+
+impl<W: BitStore> ShrAssign<u64> for BitArray<W> {
+	fn shr_assign(&mut self, n: u64) {
+		*self = *self >> n;
+	}
 }
 
 
@@ -111,10 +879,31 @@ mod tests {
 		assert_eq!(bitarray.mask(), 0b111000)
 	}	
 
+	#[test]
+	fn unchecked() {
+		let mut bits = BitArray::<u8>::zeros(4, true).unwrap();
+		unsafe {
+			bits.set_unchecked(3, true);
+			bits.toggle_unchecked(0);
+			assert!(bits.replace_unchecked(3, false));
+			assert!(bits.get_unchecked(0));
+		}
+		assert_eq!(bits.array, 0b1000_0000);
+		assert_eq!(bits.array & !bits.mask(), 0);
+	}
+
+	#[test]
+	#[cfg(debug_assertions)]
+	#[should_panic]
+	fn unchecked_out_of_range() {
+		let mut bits = BitArray::<u8>::zeros(4, false).unwrap();
+		unsafe { bits.set_unchecked(6, true) };
+	}
+
 	#[test]
 	fn trim_to() {
 		assert_eq!(
-			u64::from(BitArray{
+			u64::from(BitArray::<u64> {
 				array: 0x0ff000000000ff0,
 				left_margin: 0,
 				right_margin: 0,
@@ -123,7 +912,7 @@ mod tests {
 			0xff000000000ff0,
 		);
 		assert_eq!(
-			u64::from(BitArray{
+			u64::from(BitArray::<u64> {
 				array: 0x0ff000000000ff0,
 				left_margin: 0,
 				right_margin: 0,
@@ -131,17 +920,24 @@ mod tests {
 			}.trim_to(60)),
 			0x0ff000000000ff,
 		);
+
+		let bits = BitArray::<u8>::new(0xF0, 8, false).unwrap().trim_to(4);
+		assert_eq!(bits.array & !bits.mask(), 0);
+		assert_eq!(bits, BitArray::<u8>::zeros(4, false).unwrap());
+		let bits = BitArray::<u8>::new(0x0F, 8, true).unwrap().trim_to(4);
+		assert_eq!(bits.array & !bits.mask(), 0);
+		assert_eq!(bits, BitArray::<u8>::zeros(4, true).unwrap());
 	}
 
 	#[test]
 	fn aligned_to() {
-		let b1 = BitArray{
+		let b1: BitArray = BitArray{
 			array: 0b1111000000,
 			left_margin: 64-10,
 			right_margin: 6,
 			left_align: false,
 		};
-		let b2 = BitArray{
+		let b2: BitArray = BitArray{
 			array: 0b1111100,
 			left_margin: 64-7,
 			right_margin: 2,
@@ -179,5 +975,513 @@ mod tests {
 			left_align: b1.left_align,	
 		});
 	}
-}
 
+	#[test]
+	fn new() {
+		let bits = BitArray::<u64>::new(0b1011, 4, false).unwrap();
+		assert_eq!(bits.length(), 4);
+		assert_eq!(u64::from(bits), 0b1011);
+
+		let bits = BitArray::<u64>::new(0b1011, 4, true).unwrap();
+		assert_eq!(bits.array, 0b1011 << 60);
+		assert_eq!(u64::from(bits), 0b1011);
+
+		assert_eq!(
+			BitArray::<u64>::new(0b10000, 4, false),
+			Err(BitArrayError::BitsOutsideWindow {array: 0b10000, mask: 0b1111}),
+		);
+		assert_eq!(
+			BitArray::<u64>::new(0, 65, false),
+			Err(BitArrayError::LengthOverflow {length: 65, capacity: 64}),
+		);
+	}
+
+	#[test]
+	fn zeros_and_ones() {
+		assert_eq!(u64::from(BitArray::<u64>::zeros(12, true).unwrap()), 0);
+		assert_eq!(u64::from(BitArray::<u64>::ones(12, true).unwrap()), 0xfff);
+		assert_eq!(u64::from(BitArray::<u64>::ones(64, false).unwrap()), !0u64);
+	}
+
+	#[test]
+	fn from_margins() {
+		let bits = BitArray::<u64>::from_margins(0b0110_0000, 56, 4, false).unwrap();
+		assert_eq!(u64::from(bits), 0b0110);
+
+		assert_eq!(
+			BitArray::<u64>::from_margins(0, 40, 30, false),
+			Err(BitArrayError::MarginOverflow {
+				left_margin: 40,
+				right_margin: 30,
+				capacity: 64,
+			}),
+		);
+		assert_eq!(
+			BitArray::<u64>::from_margins(0b1, 56, 4, false),
+			Err(BitArrayError::BitsOutsideWindow {array: 0b1, mask: 0xf0}),
+		);
+	}
+
+	#[test]
+	#[allow(clippy::op_ref)]
+	fn bitwise_ops() {
+		let b1 = BitArray::<u64>::new(0b1100, 4, false).unwrap();
+		let b2 = BitArray::<u64>::new(0b1010, 4, false).unwrap();
+
+		assert_eq!(u64::from(b1 & b2), 0b1000);
+		assert_eq!(u64::from(b1 | b2), 0b1110);
+		assert_eq!(u64::from(b1 ^ b2), 0b0110);
+		assert_eq!(&b1 ^ &b2, b1 ^ b2);
+		assert_eq!(b1 & &b2, &b1 & b2);
+	}
+
+	#[test]
+	fn bitwise_ops_mixed_alignment() {
+		let b1 = BitArray::<u64>::new(0b110011, 6, true).unwrap();
+		let b2 = BitArray::<u64>::new(0b1010, 4, false).unwrap();
+
+		let bits = b1 ^ b2;
+		assert_eq!(bits.length(), 4);
+		assert!(bits.is_left_aligned());
+		assert_eq!(u64::from(bits), 0b1100 ^ 0b1010);
+		assert_eq!(bits.array & !bits.mask(), 0);
+	}
+
+	#[test]
+	fn bitwise_assign_ops() {
+		let b2 = BitArray::<u64>::new(0b1010, 4, false).unwrap();
+
+		let mut bits = BitArray::<u64>::new(0b1100, 4, false).unwrap();
+		bits &= b2;
+		assert_eq!(u64::from(bits), 0b1000);
+		bits |= &b2;
+		assert_eq!(u64::from(bits), 0b1010);
+		bits ^= b2;
+		assert_eq!(u64::from(bits), 0);
+	}
+
+	#[test]
+	fn not() {
+		let bits = BitArray::<u64>::from_margins(0b0110_0000, 56, 4, false).unwrap();
+		assert_eq!(u64::from(!bits), 0b1001);
+		assert_eq!((!bits).array, 0b1001_0000);
+		assert_eq!(!!bits, bits);
+		assert_eq!(!&bits, !bits);
+	}
+
+	#[test]
+	fn storage_words() {
+		let bits = BitArray::<u8>::new(0b101, 3, true).unwrap();
+		assert_eq!(bits.array, 0b1010_0000);
+		assert_eq!(u8::from(bits), 0b101);
+		assert_eq!(u8::from(!bits), 0b010);
+		assert_eq!(
+			BitArray::<u8>::ones(9, false),
+			Err(BitArrayError::LengthOverflow {length: 9, capacity: 8}),
+		);
+
+		let reg = BitArray::<u16>::from_margins(0x0ff0, 4, 4, false).unwrap();
+		assert_eq!(u16::from(reg), 0xff);
+
+		let id = BitArray::<u128>::new(1 << 100, 101, false).unwrap();
+		let ones = BitArray::<u128>::ones(101, true).unwrap();
+		assert_eq!(u128::from(id ^ ones), (1u128 << 100) - 1);
+
+		let word = BitArray::<usize>::ones(3, false).unwrap();
+		assert_eq!(usize::from(word), 0b111);
+	}
+
+	#[test]
+	fn get() {
+		let bits = BitArray::<u64>::from_margins(0b0110_0000, 56, 4, false).unwrap();
+		assert_eq!(
+			(0..5).map(|i| bits.get(i)).collect::<Vec<_>>(),
+			vec![Some(false), Some(true), Some(true), Some(false), None],
+		);
+
+		let bits = BitArray::<u64>::from_margins(0b0100_0000, 56, 4, true).unwrap();
+		assert_eq!(
+			(0..5).map(|i| bits.get(i)).collect::<Vec<_>>(),
+			vec![Some(false), Some(true), Some(false), Some(false), None],
+		);
+	}
+
+	#[test]
+	fn set_toggle_replace() {
+		let mut bits = BitArray::<u8>::zeros(4, true).unwrap();
+		bits.set(0, true);
+		assert_eq!(bits.array, 0b1000_0000);
+		bits.toggle(3);
+		assert_eq!(bits.array, 0b1001_0000);
+		assert!(bits.replace(0, false));
+		assert!(!bits.replace(1, true));
+		assert_eq!(u8::from(bits), 0b0101);
+
+		let mut bits = BitArray::<u8>::zeros(4, false).unwrap();
+		bits.set(0, true);
+		bits.toggle(3);
+		assert_eq!(bits.array, 0b1001);
+	}
+
+	#[test]
+	#[should_panic(expected = "index 4 out of range for length 4")]
+	fn set_out_of_range() {
+		let mut bits = BitArray::<u8>::zeros(4, false).unwrap();
+		bits.set(4, true);
+	}
+
+	#[test]
+	fn slice() {
+		let bits = BitArray::<u16>::new(0b1100_1010_0110, 12, true).unwrap();
+		let slice = bits.slice(2..7);
+		assert_eq!(slice.length(), 5);
+		assert!(slice.is_left_aligned());
+		assert_eq!(u16::from(slice), 0b00101);
+		assert_eq!(slice.array, 0b00101 << 9);
+		assert_eq!(bits.slice(..), bits);
+		assert_eq!(u16::from(bits.slice(8..=11)), 0b0110);
+
+		let bits = BitArray::<u16>::new(0b1100_1010_0110, 12, false).unwrap();
+		assert_eq!(u16::from(bits.slice(2..7)), 0b01001);
+		assert_eq!(bits.slice(2..7).array, 0b01001 << 2);
+	}
+
+	#[test]
+	#[should_panic(expected = "range 3..13 out of range for length 12")]
+	fn slice_out_of_range() {
+		BitArray::<u16>::zeros(12, false).unwrap().slice(3..13);
+	}
+
+	#[test]
+	fn fields() {
+		// version:4 | ihl:4 | dscp:6 | ecn:2
+		let mut header = BitArray::<u16>::new(0x45b8, 16, true).unwrap();
+		assert_eq!(header.extract_field(0, 4), 4);
+		assert_eq!(header.extract_field(4, 4), 5);
+		assert_eq!(header.extract_field(8, 6), 0b101110);
+		assert_eq!(header.extract_field(14, 2), 0);
+
+		header.insert_field(14, 2, 0b11);
+		header.insert_field(0, 4, 6);
+		assert_eq!(u16::from(header), 0x65bb);
+	}
+
+	#[test]
+	#[should_panic(expected = "does not fit in 2 bits")]
+	fn insert_field_too_wide() {
+		let mut bits = BitArray::<u8>::zeros(8, false).unwrap();
+		bits.insert_field(2, 2, 0b100);
+	}
+
+	#[test]
+	#[should_panic(expected = "out of range for length 8")]
+	fn extract_field_overflow() {
+		let bits = BitArray::<u8>::zeros(8, false).unwrap();
+		bits.extract_field(u64::MAX, 2);
+	}
+
+	#[test]
+	#[should_panic(expected = "out of range for length 8")]
+	fn insert_field_overflow() {
+		let mut bits = BitArray::<u8>::zeros(8, false).unwrap();
+		bits.insert_field(2, u64::MAX, 0);
+	}
+
+	#[test]
+	fn concat() {
+		let b1 = BitArray::<u8>::new(0b101, 3, true).unwrap();
+		let b2 = BitArray::<u8>::new(0b0011, 4, true).unwrap();
+		let bits = b1.concat(b2).unwrap();
+		assert!(bits.is_left_aligned());
+		assert_eq!(u8::from(bits), 0b101_0011);
+		assert_eq!(bits.array & !bits.mask(), 0);
+
+		// `b1` counts from the low end and `b2` from the high end; the
+		// result counts from the low end through both.
+		let b1 = BitArray::<u8>::new(0b101, 3, false).unwrap();
+		let bits = b1.concat(b2).unwrap();
+		assert_eq!(u8::from(bits), 0b110_0101);
+		assert_eq!(
+			bits.iter().collect::<Vec<_>>(),
+			b1.iter().chain(b2.iter()).collect::<Vec<_>>(),
+		);
+		assert_eq!(u8::from(b2.concat(b1).unwrap()), 0b001_1101);
+
+		let trimmed = BitArray::<u8>::new(0x0F, 8, true).unwrap().trim_to(4);
+		let bits = trimmed.concat(BitArray::<u8>::zeros(4, true).unwrap());
+		assert_eq!(u8::from(bits.unwrap()), 0);
+		assert_eq!(
+			b1.concat(b2).unwrap().concat(b1),
+			Err(CapacityError {length: 10, capacity: 8}),
+		);
+	}
+
+	#[test]
+	fn concat_split_roundtrip() {
+		for &left_align in &[false, true] {
+			let bits = BitArray::<u16>::new(0b1011_0010_1110, 12, left_align)
+				.unwrap();
+			for index in 0..=12 {
+				let (head, tail) = bits.split_at(index);
+				assert_eq!(head.length(), index);
+				assert_eq!(tail.length(), 12 - index);
+				assert_eq!(head.concat(tail).unwrap(), bits);
+			}
+		}
+	}
+
+	#[test]
+	fn shifts() {
+		let bits = BitArray::<u16>::from_margins(0b0110_1000, 8, 3, false).unwrap();
+		assert_eq!(u16::from(bits << 1), 0b11010);
+		assert_eq!((bits << 1).array, 0b1101_0000);
+		assert_eq!(u16::from(bits >> 2), 0b00011);
+		assert_eq!(u16::from(bits << 5), 0);
+		assert_eq!(u16::from(&bits >> 64), 0);
+
+		let mut shifted = bits;
+		shifted <<= 3;
+		assert_eq!(u16::from(shifted), 0b01000);
+		shifted >>= 1;
+		assert_eq!(u16::from(shifted), 0b00100);
+		assert_eq!(shifted.array & !shifted.mask(), 0);
+
+		// The bits just outside a trimmed or sliced window must not slide
+		// back into it.
+		let low = BitArray::<u8>::new(0xF0, 8, false).unwrap().trim_to(4);
+		assert_eq!(u8::from(low >> 1), 0);
+		assert_eq!(u8::from(low << 1), 0);
+		let high = BitArray::<u8>::new(0x0F, 8, true).unwrap().trim_to(4);
+		assert_eq!(u8::from(high << 1), 0);
+		assert_eq!(u8::from(high >> 1), 0);
+		let middle = BitArray::<u8>::new(0b1100_0011, 8, true).unwrap().slice(2..6);
+		for n in 0..=4 {
+			assert_eq!(u8::from(middle << n), 0);
+			assert_eq!(u8::from(middle >> n), 0);
+		}
+	}
+
+	#[test]
+	fn rotates() {
+		let bits = BitArray::<u16>::from_margins(0b0110_1000, 8, 3, false).unwrap();
+		assert_eq!(u16::from(bits.rotate_left(1)), 0b11010);
+		assert_eq!(u16::from(bits.rotate_left(2)), 0b10101);
+		assert_eq!(u16::from(bits.rotate_right(1)), 0b10110);
+		assert_eq!(bits.rotate_left(7), bits.rotate_left(2));
+		assert_eq!(bits.rotate_right(3), bits.rotate_left(2));
+		assert_eq!(bits.rotate_left(5), bits);
+
+		let rotated = bits.rotate_left(2);
+		assert_eq!(rotated.array & !rotated.mask(), 0);
+
+		let low = BitArray::<u8>::new(0xF0, 8, false).unwrap().trim_to(4);
+		let middle = BitArray::<u8>::new(0b1100_0011, 8, true).unwrap().slice(2..6);
+		for n in 0..=4 {
+			assert_eq!(u8::from(low.rotate_left(n)), 0);
+			assert_eq!(u8::from(low.rotate_right(n)), 0);
+			assert_eq!(u8::from(middle.rotate_left(n)), 0);
+		}
+		let ends = BitArray::<u8>::new(0b1001_1001, 8, false).unwrap().slice(2..6);
+		assert_eq!(u8::from(ends.rotate_left(1)), 0b1100);
+		assert_eq!(u8::from(ends.rotate_right(1)), 0b0011);
+	}
+
+	#[test]
+	fn counts() {
+		let bits = BitArray::<u16>::from_margins(0b0011_1000, 8, 2, true).unwrap();
+		assert_eq!(bits.count_ones(), 3);
+		assert_eq!(bits.count_zeros(), 3);
+		assert_eq!(bits.leading_zeros(), 2);
+		assert_eq!(bits.trailing_zeros(), 1);
+		assert_eq!(bits.leading_ones(), 0);
+		assert_eq!(bits.trailing_ones(), 0);
+		assert!(bits.parity());
+
+		let bits = BitArray::<u16>::from_margins(0b0011_1000, 8, 2, false).unwrap();
+		assert_eq!(bits.leading_zeros(), 1);
+		assert_eq!(bits.trailing_zeros(), 2);
+
+		let bits = BitArray::<u16>::new(0b1110, 4, true).unwrap();
+		assert_eq!(bits.leading_ones(), 3);
+		assert_eq!(bits.trailing_ones(), 0);
+		assert_eq!(bits.trailing_zeros(), 1);
+	}
+
+	#[test]
+	fn any_all_none() {
+		let zeros = BitArray::<u16>::from_margins(0, 4, 4, false).unwrap();
+		assert!(!zeros.any());
+		assert!(zeros.none());
+		assert_eq!(zeros.leading_zeros(), 8);
+		assert_eq!(zeros.count_zeros(), 8);
+
+		let ones = BitArray::<u16>::ones(8, false).unwrap();
+		assert!(ones.all());
+		assert!(ones.any());
+		assert!(!ones.parity());
+		assert!(!(ones ^ BitArray::<u16>::new(1, 8, false).unwrap()).all());
+	}
+
+	#[test]
+	fn const_api() {
+		const fn unwrap(result: Result<BitArray<u8>, BitArrayError>)
+			-> BitArray<u8>
+		{
+			match result {
+				Ok(bits) => bits,
+				Err(_) => panic!("invalid array"),
+			}
+		}
+
+		const FLAGS: BitArray<u8> = unwrap(BitArray::<u8>::new(0b1010, 4, false));
+		const HIGH: BitArray<u8> = unwrap(BitArray::<u8>::ones(2, true));
+		const MIXED: BitArray<u8> = FLAGS.and(HIGH).or(FLAGS.trim_to(1));
+		const VALUE: u8 = FLAGS.xor(HIGH).complement().value();
+		const WIDE: u64 = FLAGS.to_u64();
+
+		assert_eq!(FLAGS.mask(), 0b1111);
+		assert_eq!(HIGH.aligned_to(FLAGS).mask(), 0b0011);
+		assert_eq!(MIXED, FLAGS & HIGH | FLAGS.trim_to(1));
+		assert_eq!(VALUE, u8::from(!(FLAGS ^ HIGH)));
+		assert_eq!(VALUE, 0b10);
+		assert_eq!(WIDE, 0b1010);
+		assert_eq!(BitArray::<u64>::ones(64, true).unwrap().to_u64(), u64::MAX);
+	}
+
+	// Checks every window of a `$W` word, including the empty and full ones.
+	macro_rules! check_every_window {
+		($W:ty) => {{
+			const BITS: u64 = <$W as BitStore>::BITS;
+			for left_margin in 0..=BITS {
+				let right_margin = BITS - left_margin + 1;
+				assert_eq!(
+					BitArray::<$W>::from_margins(0, left_margin, right_margin, true),
+					Err(BitArrayError::MarginOverflow {
+						left_margin,
+						right_margin,
+						capacity: BITS,
+					}),
+				);
+
+				for right_margin in 0..=BITS - left_margin {
+					let len = BITS - left_margin - right_margin;
+					for &left_align in &[true, false] {
+						let zeros = BitArray::<$W>::from_margins(
+							0, left_margin, right_margin, left_align,
+						).unwrap();
+						let ones = BitArray::<$W>::from_margins(
+							zeros.mask(), left_margin, right_margin, left_align,
+						).unwrap();
+
+						assert_eq!(zeros.length(), len);
+						assert_eq!(ones.count_ones(), len);
+						assert_eq!(ones.mask().count_ones() as u64, len);
+						assert_eq!(
+							ones.value(),
+							if len == 0 {0} else {<$W>::MAX >> (BITS - len)},
+						);
+						assert_eq!(
+							ones,
+							BitArray::<$W>::ones(len, left_align).unwrap(),
+						);
+						assert_eq!(ones.aligned_to(zeros), ones);
+						assert_eq!(!zeros, ones);
+						assert_eq!(ones & zeros, zeros);
+						assert_eq!(ones ^ ones, zeros);
+						assert_eq!(ones.rotate_left(3), ones);
+						assert_eq!(ones << len, zeros);
+						assert_eq!(zeros.leading_zeros(), len);
+						assert_eq!(ones.iter().filter(|&b| b).count() as u64, len);
+
+						let (head, tail) = ones.split_at(len / 2);
+						assert_eq!(head.concat(tail).unwrap(), ones);
+
+						// Derive the same window from a full word, so the
+						// margins start out set, and check that no operation
+						// leaves bits behind in them.
+						let canonical =
+							|bits: BitArray<$W>| bits.array & !bits.mask() == 0;
+						let full = BitArray::<$W>::ones(BITS, left_align).unwrap();
+						let (start, end) =
+							if left_align {(left_margin, BITS - right_margin)}
+							else {(right_margin, BITS - left_margin)};
+						let sliced = full.slice(start..end);
+						assert_eq!(
+							(sliced.left_margin, sliced.right_margin),
+							(left_margin, right_margin),
+						);
+						for &bits in &[sliced, full.trim_to(len)] {
+							assert!(canonical(bits));
+							assert_eq!(bits, ones);
+							for &n in &[1, len / 2, len] {
+								assert!(canonical(bits << n));
+								assert!(canonical(bits >> n));
+								assert!(canonical(bits.rotate_left(n)));
+								assert!(canonical(bits.rotate_right(n)));
+								let kept = len - n.min(len);
+								assert_eq!((bits >> n).count_ones(), kept);
+							}
+							let half = len / 2;
+							for &align in &[true, false] {
+								let rest = BitArray::<$W>::ones(len - half, align);
+								let head = bits.trim_to(half);
+								let joined = head.concat(rest.unwrap()).unwrap();
+								assert!(canonical(joined));
+								assert_eq!(joined, ones);
+							}
+						}
+
+						let suffix = if left_align {":L"} else {":R"};
+						let literal = format!("{}{}", ones, suffix);
+						assert_eq!(literal.parse::<BitArray<$W>>(), Ok(ones));
+					}
+				}
+			}
+		}};
+	}
+
+	#[test]
+	fn every_window() {
+		check_every_window!(u8);
+		check_every_window!(u64);
+		check_every_window!(u128);
+	}
+
+	#[test]
+	fn ordering() {
+		let parse = |s: &str| s.parse::<BitArray<u8>>().unwrap();
+		let mut sorted = vec![
+			parse("0b1"), parse("0b"), parse("0b011"), parse("0b01"),
+			parse("0b0:R"), parse("0b0"), parse("0b10:R"), parse("0b0000_0001"),
+		];
+		sorted.sort();
+		assert_eq!(sorted, vec![
+			parse("0b"), parse("0b0:R"), parse("0b0"), parse("0b0000_0001"),
+			parse("0b10:R"), parse("0b01"), parse("0b011"), parse("0b1"),
+		]);
+
+		let right = BitArray::<u8>::new(0b01, 2, false).unwrap();
+		let left = BitArray::<u8>::new(0b10, 2, true).unwrap();
+		assert_eq!(right.iter().collect::<Vec<_>>(), vec![true, false]);
+		assert!(right < left);
+		assert_eq!(right.numeric_cmp(&left), Ordering::Less);
+		assert_eq!(
+			left.numeric_cmp(&BitArray::<u8>::new(0b10, 7, false).unwrap()),
+			Ordering::Equal,
+		);
+	}
+
+	#[test]
+	fn hash_matches_eq() {
+		use std::collections::HashSet;
+
+		let bits = BitArray::<u16>::new(0b101, 3, false).unwrap();
+		let moved = BitArray::<u16>::from_margins(0b1010_0000, 8, 5, false)
+			.unwrap();
+		assert_eq!(bits, moved);
+
+		let set: HashSet<_> = vec![bits, moved, !bits].into_iter().collect();
+		assert_eq!(set.len(), 2);
+		assert!(set.contains(&BitArray::<u16>::new(0b010, 3, false).unwrap()));
+	}
+}
@@ -0,0 +1,477 @@
+use core::convert::TryFrom;
+use core::hash::{Hash, Hasher};
+use core::ops::{BitAnd, BitOr, BitXor, Not};
+
+use crate::bitarray::{BitArray, BitArrayError, CapacityError};
+
+
+/// A window of up to `64 * WORDS` bits held on the stack, with the same
+/// margin model as `BitArray`.
+///
+/// `words[0]` holds the least significant bits, so the left margin counts
+/// down from the top of the last word and the right margin up from the
+/// bottom of the first.
+#[derive(Debug, Clone, Copy)]
+pub struct BitArrayN<const WORDS: usize> {
+	words: [u64; WORDS],
+	left_margin: u64,
+	right_margin: u64,
+	left_align: bool,
+}
+
+impl<const WORDS: usize> PartialEq for BitArrayN<WORDS> {
+	fn eq(&self, other: &Self) -> bool {
+		self.left_align == other.left_align
+		&& self.value() == other.value()
+		&& self.length() == other.length()
+	}
+}
+
+impl<const WORDS: usize> Eq for BitArrayN<WORDS> {}
+
+impl<const WORDS: usize> Hash for BitArrayN<WORDS> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.left_align.hash(state);
+		self.length().hash(state);
+		self.value().hash(state);
+	}
+}
+
+impl<const WORDS: usize> BitArrayN<WORDS> {
+	/// The number of bits across all of the words.
+	pub const BITS: u64 = 64 * WORDS as u64;
+
+	/// Creates a `len`-bit array holding `value`, least significant word
+	/// first, flush against the aligned side.
+	pub fn new(value: [u64; WORDS], len: u64, left_align: bool)
+		-> Result<Self, BitArrayError>
+	{
+		if len > Self::BITS {
+			return Err(BitArrayError::LengthOverflow {
+				length: len,
+				capacity: Self::BITS,
+			});
+		}
+
+		check_window(value, ones_below(len))?;
+
+		let (left_margin, right_margin) =
+			if left_align {(0, Self::BITS - len)}
+			else {(Self::BITS - len, 0)};
+		Self::from_margins(
+			shl(value, right_margin), left_margin, right_margin, left_align,
+		)
+	}
+
+	/// Creates a `len`-bit array with every bit cleared.
+	pub fn zeros(len: u64, left_align: bool) -> Result<Self, BitArrayError> {
+		Self::new([0; WORDS], len, left_align)
+	}
+
+	/// Creates a `len`-bit array with every bit set.
+	pub fn ones(len: u64, left_align: bool) -> Result<Self, BitArrayError> {
+		let mut bits = Self::zeros(len, left_align)?;
+		bits.words = bits.mask();
+		Ok(bits)
+	}
+
+	/// Wraps raw words, using the margins to select the window of
+	/// significant bits.
+	pub fn from_margins(
+		words: [u64; WORDS],
+		left_margin: u64,
+		right_margin: u64,
+		left_align: bool,
+	) -> Result<Self, BitArrayError> {
+		if left_margin.saturating_add(right_margin) > Self::BITS {
+			return Err(BitArrayError::MarginOverflow {
+				left_margin,
+				right_margin,
+				capacity: Self::BITS,
+			});
+		}
+
+		let bits = Self {words, left_margin, right_margin, left_align};
+		check_window(words, bits.mask())?;
+		Ok(bits)
+	}
+
+	pub fn length(&self) -> u64 {
+		Self::BITS - (self.left_margin + self.right_margin)
+	}
+
+	pub fn is_left_aligned(&self) -> bool {
+		self.left_align
+	}
+
+	/// The number of unused bits above the window.
+	pub fn left_margin(&self) -> u64 {
+		self.left_margin
+	}
+
+	/// The number of unused bits below the window.
+	pub fn right_margin(&self) -> u64 {
+		self.right_margin
+	}
+
+	/// Returns the window shifted down to the least significant bits.
+	pub fn value(&self) -> [u64; WORDS] {
+		shr(self.words, self.right_margin)
+	}
+
+	/// Selects the window within the words.
+	pub fn mask(&self) -> [u64; WORDS] {
+		let below: [u64; WORDS] = ones_below(self.right_margin);
+		let mut mask = ones_below(Self::BITS - self.left_margin);
+		for (word, below) in mask.iter_mut().zip(&below) {
+			*word &= !below;
+		}
+		mask
+	}
+
+	/// Returns the bit at `index`, counting from the aligned side, or `None`
+	/// if `index` is out of range.
+	pub fn get(&self, index: u64) -> Option<bool> {
+		if index >= self.length() {
+			return None;
+		}
+		let (word, bit) = self.locate(index);
+		Some(self.words[word] & bit != 0)
+	}
+
+	/// Sets the bit at `index`, counting from the aligned side.
+	///
+	/// Panics if `index` is out of range.
+	pub fn set(&mut self, index: u64, value: bool) {
+		assert!(index < self.length(), "index {} out of range for length {}",
+			index, self.length());
+		let (word, bit) = self.locate(index);
+		if value {
+			self.words[word] |= bit;
+		} else {
+			self.words[word] &= !bit;
+		}
+	}
+
+	pub fn count_ones(&self) -> u64 {
+		self.words.iter().map(|word| word.count_ones() as u64).sum()
+	}
+
+	// Finds the word and bit at `index`, counting from the aligned side.
+	fn locate(&self, index: u64) -> (usize, u64) {
+		let position =
+			if self.left_align {Self::BITS - self.left_margin - 1 - index}
+			else {self.right_margin + index};
+		((position / 64) as usize, 1 << (position % 64))
+	}
+
+	/// Moves the window to the aligned side of `bits`, keeping its value.
+	pub fn aligned_to(self, bits: Self) -> Self {
+		if bits.left_align {
+			Self {
+				words: shr(shl(self.words, self.left_margin), bits.left_margin),
+				left_margin: bits.left_margin,
+				right_margin: Self::BITS.saturating_sub(
+					bits.left_margin + self.length()
+				),
+				left_align: self.left_align,
+			}
+		} else {
+			Self {
+				words: shl(shr(self.words, self.right_margin), bits.right_margin),
+				left_margin: Self::BITS.saturating_sub(
+					bits.right_margin + self.length()
+				),
+				right_margin: bits.right_margin,
+				left_align: self.left_align,
+			}
+		}
+	}
+
+	pub fn trim_to(self, new_len: u64) -> Self {
+		if new_len >= self.length() {
+			return self;
+		}
+
+		let mut bits = Self {
+			left_margin:
+				if self.left_align {self.left_margin}
+				else {Self::BITS - self.right_margin - new_len},
+			right_margin:
+				if !self.left_align {self.right_margin}
+				else {Self::BITS - self.left_margin - new_len},
+			..self
+		};
+		bits.words = and_words(bits.words, bits.mask());
+		bits
+	}
+
+	/// Combines the overlapping bits of two arrays word by word, as
+	/// `BitArray::apply_binary` does within a single word.
+	pub fn apply_binary<F>(&self, func: F, bits: Self) -> Self
+		where F: Fn(u64, u64) -> u64
+	{
+		let bits = bits.aligned_to(*self);
+		let self_ = self.trim_to(bits.length());
+
+		let mut window = Self {
+			words: [0; WORDS],
+			left_margin: u64::max(self_.left_margin, bits.left_margin),
+			right_margin: u64::max(self_.right_margin, bits.right_margin),
+			left_align: self_.left_align,
+		};
+		let mask = window.mask();
+		for (i, word) in window.words.iter_mut().enumerate() {
+			*word = func(self_.words[i], bits.words[i]) & mask[i];
+		}
+		window
+	}
+}
+
+// Shifts the words towards the most significant end, clearing the word
+// once `n` spans all of them.
+fn shl<const WORDS: usize>(words: [u64; WORDS], n: u64) -> [u64; WORDS] {
+	let mut result = [0; WORDS];
+	let (skip, shift) = ((n / 64) as usize, n % 64);
+	for (i, word) in result.iter_mut().enumerate().skip(skip) {
+		let j = i - skip;
+		*word = words[j] << shift;
+		if shift != 0 && j > 0 {
+			*word |= words[j - 1] >> (64 - shift);
+		}
+	}
+	result
+}
+
+// Shifts the words towards the least significant end.
+fn shr<const WORDS: usize>(words: [u64; WORDS], n: u64) -> [u64; WORDS] {
+	let mut result = [0; WORDS];
+	let (skip, shift) = ((n / 64) as usize, n % 64);
+	for (i, word) in result.iter_mut().enumerate() {
+		let j = i + skip;
+		if j >= WORDS {
+			break;
+		}
+		*word = words[j] >> shift;
+		if shift != 0 && j + 1 < WORDS {
+			*word |= words[j + 1] << (64 - shift);
+		}
+	}
+	result
+}
+
+// Reports the first word with set bits outside of `mask`.
+fn check_window<const WORDS: usize>(words: [u64; WORDS], mask: [u64; WORDS])
+	-> Result<(), BitArrayError>
+{
+	match (0..WORDS).find(|&i| words[i] & !mask[i] != 0) {
+		Some(i) => Err(BitArrayError::BitsOutsideWindow {
+			array: words[i] as u128,
+			mask: mask[i] as u128,
+		}),
+		None => Ok(()),
+	}
+}
+
+// Sets the lowest `n` bits.
+fn ones_below<const WORDS: usize>(n: u64) -> [u64; WORDS] {
+	let mut result = [0; WORDS];
+	for (i, word) in result.iter_mut().enumerate() {
+		let low = 64 * i as u64;
+		*word =
+			if n >= low + 64 {!0}
+			else if n > low {!0 >> (64 - (n - low))}
+			else {0};
+	}
+	result
+}
+
+fn and_words<const WORDS: usize>(lhs: [u64; WORDS], rhs: [u64; WORDS])
+	-> [u64; WORDS]
+{
+	let mut result = lhs;
+	for (word, rhs) in result.iter_mut().zip(&rhs) {
+		*word &= rhs;
+	}
+	result
+}
+
+impl<const WORDS: usize> BitAnd for BitArrayN<WORDS> {
+	type Output = Self;
+
+	fn bitand(self, bits: Self) -> Self {
+		self.apply_binary(|x, y| x & y, bits)
+	}
+}
+
+impl<const WORDS: usize> BitOr for BitArrayN<WORDS> {
+	type Output = Self;
+
+	fn bitor(self, bits: Self) -> Self {
+		self.apply_binary(|x, y| x | y, bits)
+	}
+}
+
+impl<const WORDS: usize> BitXor for BitArrayN<WORDS> {
+	type Output = Self;
+
+	fn bitxor(self, bits: Self) -> Self {
+		self.apply_binary(|x, y| x ^ y, bits)
+	}
+}
+
+impl<const WORDS: usize> Not for BitArrayN<WORDS> {
+	type Output = Self;
+
+	fn not(self) -> Self {
+		let mut words = self.words;
+		for word in words.iter_mut() {
+			*word = !*word;
+		}
+		Self {words: and_words(words, self.mask()), ..self}
+	}
+}
+
+/// Widens a single-word array, keeping its alignment and value. Fails only
+/// if `WORDS` is zero and the array is not empty.
+impl<const WORDS: usize> TryFrom<BitArray> for BitArrayN<WORDS> {
+	type Error = CapacityError;
+
+	fn try_from(bits: BitArray) -> Result<Self, CapacityError> {
+		let capacity_error = CapacityError {
+			length: bits.length(),
+			capacity: Self::BITS,
+		};
+		let mut value = [0; WORDS];
+		match value.first_mut() {
+			Some(word) => *word = u64::from(bits),
+			None if bits.length() > 0 => return Err(capacity_error),
+			None => {},
+		}
+		Self::new(value, bits.length(), bits.is_left_aligned())
+			.map_err(|_| capacity_error)
+	}
+}
+
+/// Narrows to a single-word array, keeping its alignment and value. Fails if
+/// the window is longer than 64 bits.
+impl<const WORDS: usize> TryFrom<BitArrayN<WORDS>> for BitArray {
+	type Error = CapacityError;
+
+	fn try_from(bits: BitArrayN<WORDS>) -> Result<Self, CapacityError> {
+		if bits.length() > 64 {
+			return Err(CapacityError {length: bits.length(), capacity: 64});
+		}
+		let value = bits.value().first().copied().unwrap_or(0);
+		Ok(BitArray::<u64>::new(value, bits.length(), bits.is_left_aligned())
+			.expect("the window fits within a single word"))
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	type Bits256 = BitArrayN<4>;
+
+	#[test]
+	fn new() {
+		let bits = Bits256::new([0b1011, 0, 1, 0], 129, true).unwrap();
+		assert_eq!(bits.length(), 129);
+		assert_eq!(bits.right_margin(), 127);
+		assert_eq!(bits.value(), [0b1011, 0, 1, 0]);
+		assert_eq!(bits.get(0), Some(true));
+		assert_eq!(bits.get(125), Some(true));
+		assert_eq!(bits.get(126), Some(false));
+		assert_eq!(bits.get(128), Some(true));
+		assert_eq!(bits.get(129), None);
+
+		assert_eq!(
+			Bits256::new([0, 0, 2, 0], 129, true),
+			Err(BitArrayError::BitsOutsideWindow {array: 2, mask: 1}),
+		);
+		assert_eq!(
+			Bits256::zeros(257, false),
+			Err(BitArrayError::LengthOverflow {length: 257, capacity: 256}),
+		);
+		assert_eq!(Bits256::ones(256, false).unwrap().count_ones(), 256);
+		assert_eq!(Bits256::ones(0, true).unwrap().count_ones(), 0);
+	}
+
+	#[test]
+	fn set() {
+		let mut bits = Bits256::zeros(200, false).unwrap();
+		bits.set(0, true);
+		bits.set(64, true);
+		bits.set(199, true);
+		assert_eq!(bits.value(), [1, 1, 0, 1 << 7]);
+		bits.set(64, false);
+		assert_eq!(bits.count_ones(), 2);
+	}
+
+	#[test]
+	fn aligned_to_and_trim_to() {
+		let bits = Bits256::new([!0, 0b101, 0, 0], 67, false).unwrap();
+		let target = Bits256::zeros(10, true).unwrap();
+		let moved = bits.aligned_to(target);
+		assert_eq!(moved.left_margin(), 0);
+		assert_eq!(moved.right_margin(), 256 - 67);
+		assert_eq!(moved.value(), bits.value());
+		assert_eq!(moved, bits);
+
+		let trimmed = bits.trim_to(65);
+		assert_eq!(trimmed.length(), 65);
+		assert_eq!(trimmed.value(), [!0, 1, 0, 0]);
+		assert_eq!(trimmed.trim_to(65), trimmed);
+		assert_eq!(bits.trim_to(100), bits);
+	}
+
+	#[test]
+	fn bitwise_ops() {
+		let a = Bits256::new([0xff00, 0, 0b11, 0], 130, false).unwrap();
+		let b = Bits256::new([0x0ff0, 0, 0b10, 0], 130, true).unwrap();
+		assert_eq!((a & b).value(), [0x0f00, 0, 0b10, 0]);
+		assert_eq!((a | b).value(), [0xfff0, 0, 0b11, 0]);
+		assert_eq!((a ^ b).value(), [0xf0f0, 0, 0b01, 0]);
+		assert!(!(a ^ b).is_left_aligned());
+		assert_eq!((!a).value(), [!0xff00, !0, 0, 0]);
+
+		let short = Bits256::ones(70, false).unwrap();
+		assert_eq!((a & short).length(), 70);
+		assert_eq!((a & short).value(), [0xff00, 0, 0, 0]);
+	}
+
+	#[test]
+	fn matches_bitarray() {
+		for &left_align in &[true, false] {
+			let a = BitArray::<u64>::new(0x0123_4567_89ab, 48, left_align).unwrap();
+			let b = BitArray::<u64>::new(0xffff_0000, 33, !left_align).unwrap();
+			let (wide_a, wide_b) = (
+				BitArrayN::<2>::try_from(a).unwrap(),
+				BitArrayN::<2>::try_from(b).unwrap(),
+			);
+			for &(wide, narrow) in &[
+				(wide_a ^ wide_b, a ^ b),
+				(wide_a & wide_b, a & b),
+				(!wide_a | wide_b, !a | b),
+				(wide_a.trim_to(20), a.trim_to(20)),
+				(wide_b.aligned_to(wide_a), b.aligned_to(a)),
+			] {
+				assert_eq!(BitArray::try_from(wide), Ok(narrow));
+				assert_eq!(BitArrayN::try_from(narrow), Ok(wide));
+			}
+		}
+
+		let wide = BitArrayN::<2>::ones(65, true).unwrap();
+		assert_eq!(
+			BitArray::try_from(wide),
+			Err(CapacityError {length: 65, capacity: 64}),
+		);
+		let bits = BitArray::<u64>::ones(1, true).unwrap();
+		assert_eq!(
+			BitArrayN::<0>::try_from(bits),
+			Err(CapacityError {length: 1, capacity: 0}),
+		);
+	}
+}
@@ -0,0 +1,317 @@
+use core::convert::From;
+use core::hash::{Hash, Hasher};
+use core::iter::Extend;
+
+use crate::bitarray::BitArray;
+use crate::bitstore::BitStore;
+use crate::iter::Indices;
+
+
+/// A set of small integers, held as the set bits of a right-aligned
+/// `BitArray` so that integer `i` is bit `i` of the word.
+///
+/// The set can hold the integers below the length of its array. When two
+/// sets differ in capacity, `union`, `difference` and `symmetric_difference`
+/// widen the smaller one first so that no member is lost, while
+/// `intersection` only covers the integers that both can hold. Likewise,
+/// sets compare and hash by their members alone, whatever their capacities.
+#[derive(Debug, Clone, Copy)]
+pub struct BitSet<W: BitStore = u64> {
+	bits: BitArray<W>,
+}
+
+impl<W: BitStore> PartialEq for BitSet<W> {
+	fn eq(&self, other: &Self) -> bool {
+		W::value(&self.bits) == W::value(&other.bits)
+	}
+}
+
+impl<W: BitStore> Eq for BitSet<W> {}
+
+impl<W: BitStore> Hash for BitSet<W> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		W::value(&self.bits).hash(state);
+	}
+}
+
+impl<W: BitStore> From<BitArray<W>> for BitSet<W> {
+	/// Collects the indices of the set bits; a left-aligned array is
+	/// realigned so that each index keeps its meaning.
+	fn from(bits: BitArray<W>) -> Self {
+		if !bits.is_left_aligned() {
+			return Self {bits};
+		}
+		let mut set = Self::with_capacity(bits.length());
+		set.extend(bits.iter_ones());
+		set
+	}
+}
+
+impl<W: BitStore> From<BitSet<W>> for BitArray<W> {
+	fn from(set: BitSet<W>) -> Self {
+		set.bits
+	}
+}
+
+impl<W: BitStore> BitSet<W> {
+	/// Makes an empty set that can hold the integers below `W::BITS`.
+	pub fn new() -> Self {
+		Self::with_capacity(W::BITS)
+	}
+
+	/// Makes an empty set that can hold the integers below `capacity`.
+	///
+	/// Panics if `capacity` exceeds `W::BITS`.
+	pub fn with_capacity(capacity: u64) -> Self {
+		let bits = W::new(W::ZERO, capacity, false);
+		Self {bits: bits.expect("capacity exceeds the word size")}
+	}
+
+	pub fn as_bit_array(&self) -> &BitArray<W> {
+		&self.bits
+	}
+
+	/// The number of integers the set can hold.
+	pub fn capacity(&self) -> u64 {
+		self.bits.length()
+	}
+
+	pub fn len(&self) -> u64 {
+		self.bits.count_ones()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.bits.none()
+	}
+
+	pub fn contains(&self, value: u64) -> bool {
+		self.bits.get(value).unwrap_or(false)
+	}
+
+	/// Adds `value` to the set, returning whether it was absent.
+	///
+	/// Panics if `value` is not below `capacity()`.
+	pub fn insert(&mut self, value: u64) -> bool {
+		!self.bits.replace(value, true)
+	}
+
+	/// Removes `value` from the set, returning whether it was present.
+	pub fn remove(&mut self, value: u64) -> bool {
+		self.contains(value) && self.bits.replace(value, false)
+	}
+
+	pub fn clear(&mut self) {
+		self.bits = self.bits.apply_binary(|_, _| W::ZERO, self.bits);
+	}
+
+	/// Iterates over the members in ascending order.
+	pub fn iter(&self) -> Indices<W> {
+		self.bits.iter_ones()
+	}
+
+	pub fn union(&self, other: &Self) -> Self {
+		self.combine(other, |x, y| x | y)
+	}
+
+	/// The result has the smaller of the two capacities.
+	pub fn intersection(&self, other: &Self) -> Self {
+		Self {bits: self.bits.apply_binary(|x, y| x & y, other.bits)}
+	}
+
+	pub fn difference(&self, other: &Self) -> Self {
+		self.combine(other, |x, y| x & !y)
+	}
+
+	pub fn symmetric_difference(&self, other: &Self) -> Self {
+		self.combine(other, |x, y| x ^ y)
+	}
+
+	/// Checks that every member of `self` is in `other`, including any that
+	/// `other` is too short to hold.
+	pub fn is_subset(&self, other: &Self) -> bool {
+		self.intersection(other).len() == self.len()
+	}
+
+	pub fn is_superset(&self, other: &Self) -> bool {
+		other.is_subset(self)
+	}
+
+	pub fn is_disjoint(&self, other: &Self) -> bool {
+		self.intersection(other).is_empty()
+	}
+
+	// Applies `func` with both sets widened to the larger capacity.
+	fn combine<F>(&self, other: &Self, func: F) -> Self
+		where F: Fn(W, W) -> W
+	{
+		let capacity = u64::max(self.capacity(), other.capacity());
+		let bits = self.widened(capacity);
+		Self {bits: bits.apply_binary(func, other.widened(capacity))}
+	}
+
+	fn widened(&self, capacity: u64) -> BitArray<W> {
+		if capacity == self.capacity() {
+			return self.bits;
+		}
+		let bits = W::new(W::value(&self.bits), capacity, false);
+		bits.expect("capacity exceeds the word size")
+	}
+}
+
+impl<W: BitStore> Default for BitSet<W> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<W: BitStore> IntoIterator for BitSet<W> {
+	type Item = u64;
+	type IntoIter = Indices<W>;
+
+	fn into_iter(self) -> Indices<W> {
+		self.iter()
+	}
+}
+
+impl<W: BitStore> IntoIterator for &BitSet<W> {
+	type Item = u64;
+	type IntoIter = Indices<W>;
+
+	fn into_iter(self) -> Indices<W> {
+		self.iter()
+	}
+}
+
+impl<W: BitStore> Extend<u64> for BitSet<W> {
+	/// Panics if any value is not below `capacity()`.
+	fn extend<I: IntoIterator<Item = u64>>(&mut self, iter: I) {
+		for value in iter {
+			self.insert(value);
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::hash_map::DefaultHasher;
+
+	fn set_of(values: &[u64], capacity: u64) -> BitSet {
+		let mut set = BitSet::with_capacity(capacity);
+		set.extend(values.iter().copied());
+		set
+	}
+
+	#[test]
+	fn insert_and_remove() {
+		let mut set = BitSet::<u64>::new();
+		assert_eq!(set.capacity(), 64);
+		assert!(set.is_empty());
+		assert!(set.insert(3));
+		assert!(set.insert(63));
+		assert!(!set.insert(3));
+		assert_eq!(set.len(), 2);
+		assert!(set.contains(63));
+		assert!(!set.contains(4));
+		assert!(!set.contains(64));
+		assert_eq!(u64::from(BitArray::from(set)), 1 << 63 | 1 << 3);
+
+		assert!(set.remove(3));
+		assert!(!set.remove(3));
+		assert!(!set.remove(100));
+		assert_eq!(set.iter().collect::<Vec<_>>(), [63]);
+		set.clear();
+		assert!(set.is_empty());
+		assert_eq!(set.capacity(), 64);
+	}
+
+	#[test]
+	fn from_bit_array() {
+		let bits: BitArray = "0b0110_0001".parse().unwrap();
+		let set = BitSet::from(bits);
+		assert_eq!(set.capacity(), 8);
+		assert_eq!(set.iter().collect::<Vec<_>>(), [1, 2, 7]);
+		assert_eq!(u64::from(*set.as_bit_array()), 0b1000_0110);
+
+		let bits: BitArray = "0b0110_0001:R".parse().unwrap();
+		assert_eq!(BitSet::from(bits).iter().collect::<Vec<_>>(), [0, 5, 6]);
+		assert_eq!(BitSet::<u8>::default(), BitSet::with_capacity(8));
+	}
+
+	#[test]
+	#[should_panic]
+	fn insert_out_of_range() {
+		set_of(&[], 10).insert(10);
+	}
+
+	#[test]
+	fn iter() {
+		let set = set_of(&[9, 0, 41, 5], 50);
+		assert_eq!(set.iter().collect::<Vec<_>>(), [0, 5, 9, 41]);
+		assert_eq!(set.into_iter().rev().collect::<Vec<_>>(), [41, 9, 5, 0]);
+		assert_eq!((&set).into_iter().len(), 4);
+	}
+
+	#[test]
+	fn set_algebra() {
+		let a = set_of(&[1, 2, 3, 10], 64);
+		let b = set_of(&[2, 3, 4, 63], 64);
+		let members = |set: BitSet| set.iter().collect::<Vec<_>>();
+
+		assert_eq!(members(a.union(&b)), [1, 2, 3, 4, 10, 63]);
+		assert_eq!(members(a.intersection(&b)), [2, 3]);
+		assert_eq!(members(a.difference(&b)), [1, 10]);
+		assert_eq!(members(b.difference(&a)), [4, 63]);
+		assert_eq!(members(a.symmetric_difference(&b)), [1, 4, 10, 63]);
+
+		assert!(!a.is_subset(&b) && !a.is_disjoint(&b));
+		assert!(a.intersection(&b).is_subset(&a));
+		assert!(a.union(&b).is_superset(&b));
+		assert!(a.difference(&b).is_disjoint(&b));
+		assert!(a.is_subset(&a) && a.is_superset(&a));
+	}
+
+	#[test]
+	fn different_capacities() {
+		let short = set_of(&[1, 6], 8);
+		let long = set_of(&[1, 6, 20], 32);
+
+		let members = |set: BitSet| set.iter().collect::<Vec<_>>();
+		for &union in &[short.union(&long), long.union(&short)] {
+			assert_eq!(union.capacity(), 32);
+			assert_eq!(members(union), [1, 6, 20]);
+		}
+		assert_eq!(members(long.difference(&short)), [20]);
+		assert_eq!(short.difference(&long).capacity(), 32);
+		assert!(short.difference(&long).is_empty());
+		let other = set_of(&[2, 6, 31], 32);
+		assert_eq!(members(short.symmetric_difference(&other)), [1, 2, 31]);
+		assert_eq!(members(other.symmetric_difference(&short)), [1, 2, 31]);
+		let common = short.intersection(&long);
+		assert_eq!(common.capacity(), 8);
+		assert_eq!(members(common), [1, 6]);
+		assert!(short.is_subset(&long));
+		assert!(!long.is_subset(&short));
+		assert!(long.is_superset(&short));
+		assert!(set_of(&[20], 32).is_disjoint(&short));
+	}
+
+	#[test]
+	fn equality_ignores_capacity() {
+		let hash = |set: &BitSet| {
+			let mut hasher = DefaultHasher::new();
+			set.hash(&mut hasher);
+			hasher.finish()
+		};
+		let short = set_of(&[1], 8);
+		let long = set_of(&[1], 16);
+		assert_eq!(short, long);
+		assert_eq!(hash(&short), hash(&long));
+		assert_ne!(short, set_of(&[1, 9], 16));
+
+		let wide = set_of(&[1, 12], 16);
+		assert_eq!(short.union(&wide), wide);
+		assert_eq!(wide.intersection(&short), short);
+	}
+}
@@ -0,0 +1,125 @@
+use core::fmt::{Binary, Debug};
+use core::hash::Hash;
+use core::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr};
+
+use crate::bitarray::{BitArray, BitArrayError};
+
+
+mod private {
+	pub trait Sealed {}
+}
+
+/// An unsigned integer that can back a `BitArray`.
+///
+/// This is sealed: it is only implemented for the native unsigned integers.
+pub trait BitStore:
+	private::Sealed
+	+ Copy + Eq + Hash + Debug + Binary
+	+ BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self>
+	+ Not<Output = Self>
+	+ Shl<u64, Output = Self> + Shr<u64, Output = Self>
+{
+	/// The number of bits in the word.
+	const BITS: u64;
+	/// The word with every bit cleared.
+	const ZERO: Self;
+	/// The word with only the least significant bit set.
+	const ONE: Self;
+	/// The word with every bit set.
+	const ONES: Self;
+
+	/// Widens the word to the largest native integer.
+	fn to_u128(self) -> u128;
+	/// Truncates the largest native integer to the word.
+	fn from_u128(value: u128) -> Self;
+
+	/// Counts the set bits.
+	fn count_ones(self) -> u64;
+	/// Counts the cleared bits above the most significant set bit.
+	fn leading_zeros(self) -> u64;
+	/// Counts the cleared bits below the least significant set bit.
+	fn trailing_zeros(self) -> u64;
+
+	// Hooks into the `const fn`s that `BitArray` implements separately for
+	// each word type, so that generic code can reach them.
+	#[doc(hidden)]
+	fn new(value: Self, len: u64, left_align: bool)
+		-> Result<BitArray<Self>, BitArrayError>;
+	#[doc(hidden)]
+	fn value(bits: &BitArray<Self>) -> Self;
+	#[doc(hidden)]
+	fn mask(bits: &BitArray<Self>) -> Self;
+	#[doc(hidden)]
+	fn trim_to(bits: BitArray<Self>, new_len: u64) -> BitArray<Self>;
+	#[doc(hidden)]
+	fn aligned_to(bits: BitArray<Self>, other: BitArray<Self>)
+		-> BitArray<Self>;
+	#[doc(hidden)]
+	fn overlap(bits: BitArray<Self>, other: BitArray<Self>)
+		-> (BitArray<Self>, Self, Self);
+}
+
+macro_rules! impl_bitstore {
+	($($W:ty),*) => {$(
+		impl private::Sealed for $W {}
+
+		impl BitStore for $W {
+			const BITS: u64 = <$W>::BITS as u64;
+			const ZERO: Self = 0;
+			const ONE: Self = 1;
+			const ONES: Self = !0;
+
+			fn to_u128(self) -> u128 {
+				self as u128
+			}
+
+			fn from_u128(value: u128) -> Self {
+				value as $W
+			}
+
+			fn count_ones(self) -> u64 {
+				<$W>::count_ones(self) as u64
+			}
+
+			fn leading_zeros(self) -> u64 {
+				<$W>::leading_zeros(self) as u64
+			}
+
+			fn trailing_zeros(self) -> u64 {
+				<$W>::trailing_zeros(self) as u64
+			}
+
+			fn new(value: Self, len: u64, left_align: bool)
+				-> Result<BitArray<Self>, BitArrayError>
+			{
+				BitArray::<$W>::new(value, len, left_align)
+			}
+
+			fn value(bits: &BitArray<Self>) -> Self {
+				bits.value()
+			}
+
+			fn mask(bits: &BitArray<Self>) -> Self {
+				bits.mask()
+			}
+
+			fn trim_to(bits: BitArray<Self>, new_len: u64) -> BitArray<Self> {
+				bits.trim_to(new_len)
+			}
+
+			fn aligned_to(bits: BitArray<Self>, other: BitArray<Self>)
+				-> BitArray<Self>
+			{
+				bits.aligned_to(other)
+			}
+
+			fn overlap(bits: BitArray<Self>, other: BitArray<Self>)
+				-> (BitArray<Self>, Self, Self)
+			{
+				bits.overlap(other)
+			}
+		}
+	)*};
+}
+
+impl_bitstore!(u8, u16, u32, u64, u128, usize);
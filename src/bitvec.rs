@@ -0,0 +1,460 @@
+use core::convert::From;
+use core::iter::Extend;
+use core::ops::{
+	BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not,
+};
+
+use alloc::vec::Vec;
+
+use crate::bitarray::BitArray;
+
+
+/// A growable bit sequence with the same alignment semantics as `BitArray`.
+///
+/// Bit index 0 sits on the aligned side: the most significant end when
+/// `left_align` is set, the least significant end otherwise. The words are
+/// kept flush against the aligned side, so the only margin is the unused
+/// tail of the last word.
+#[derive(Debug, Clone, Default)]
+pub struct BitVec {
+	words: Vec<u64>,
+	len: u64,
+	left_align: bool,
+}
+
+impl PartialEq for BitVec {
+	fn eq(&self, other: &Self) -> bool {
+		self.left_align == other.left_align
+		&& self.len == other.len
+		&& self.words == other.words
+	}
+}
+
+impl From<BitArray> for BitVec {
+	fn from(ba: BitArray) -> BitVec {
+		let mut bits = BitVec::new(ba.is_left_aligned());
+		if ba.length() == 0 {
+			return bits;
+		}
+
+		let value = u64::from(ba);
+		bits.words.push(
+			if ba.is_left_aligned() {value << (64 - ba.length())}
+			else {value}
+		);
+		bits.len = ba.length();
+		bits
+	}
+}
+
+impl BitVec {
+	pub fn new(left_align: bool) -> Self {
+		Self {
+			words: Vec::new(),
+			len: 0,
+			left_align,
+		}
+	}
+
+	pub fn with_capacity(bits: u64, left_align: bool) -> Self {
+		Self {
+			words: Vec::with_capacity(word_count(bits)),
+			len: 0,
+			left_align,
+		}
+	}
+
+	pub fn length(&self) -> u64 {
+		self.len
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	pub fn is_left_aligned(&self) -> bool {
+		self.left_align
+	}
+
+	pub fn get(&self, index: u64) -> Option<bool> {
+		if index >= self.len {
+			return None;
+		}
+		let (word, bit) = self.locate(index);
+		Some(self.words[word] & bit != 0)
+	}
+
+	pub fn set(&mut self, index: u64, value: bool) {
+		assert!(index < self.len, "index {} out of range for length {}",
+			index, self.len);
+		let (word, bit) = self.locate(index);
+		if value {
+			self.words[word] |= bit;
+		} else {
+			self.words[word] &= !bit;
+		}
+	}
+
+	/// Appends a bit to the non-aligned end.
+	pub fn push(&mut self, value: bool) {
+		if self.len.is_multiple_of(64) {
+			self.words.push(0);
+		}
+		self.len += 1;
+		self.set(self.len - 1, value);
+	}
+
+	/// Removes the bit furthest from the aligned side.
+	pub fn pop(&mut self) -> Option<bool> {
+		let value = self.get(self.len.checked_sub(1)?)?;
+		self.truncate(self.len - 1);
+		Some(value)
+	}
+
+	/// Inserts a bit at `index`, moving every later bit one place away from
+	/// the aligned side.
+	pub fn insert(&mut self, index: u64, value: bool) {
+		assert!(index <= self.len, "index {} out of range for length {}",
+			index, self.len);
+		if self.len.is_multiple_of(64) {
+			self.words.push(0);
+		}
+
+		let first = (index / 64) as usize;
+		for i in (first+1..self.words.len()).rev() {
+			let carry = self.words[i-1] & self.offset_bit(63) != 0;
+			self.words[i] = self.move_up(self.words[i])
+				| if carry {self.offset_bit(0)} else {0};
+		}
+		let low = self.offset_mask(index % 64);
+		self.words[first] = (self.words[first] & low)
+			| (self.move_up(self.words[first]) & !low);
+
+		self.len += 1;
+		self.set(index, value);
+	}
+
+	/// Removes the bit at `index`, moving every later bit one place towards
+	/// the aligned side.
+	pub fn remove(&mut self, index: u64) -> bool {
+		assert!(index < self.len, "index {} out of range for length {}",
+			index, self.len);
+		let value = self.get(index).unwrap();
+
+		let first = (index / 64) as usize;
+		let low = self.offset_mask(index % 64);
+		for i in first..self.words.len() {
+			let carry = self.words.get(i+1)
+				.is_some_and(|&w| w & self.offset_bit(0) != 0);
+			let moved = self.move_down(self.words[i])
+				| if carry {self.offset_bit(63)} else {0};
+			self.words[i] =
+				if i == first {(self.words[i] & low) | (moved & !low)}
+				else {moved};
+		}
+
+		self.truncate(self.len - 1);
+		value
+	}
+
+	/// Shortens the vector to `new_len` bits, keeping the aligned side.
+	///
+	/// This is the growable analogue of `BitArray::trim_to`.
+	pub fn truncate(&mut self, new_len: u64) {
+		if new_len >= self.len {
+			return;
+		}
+
+		self.words.truncate(word_count(new_len));
+		self.len = new_len;
+		self.clear_tail();
+	}
+
+	/// Returns the same bits with the aligned side moved to the other end of
+	/// the value, which reverses the index order.
+	pub fn realigned(&self, left_align: bool) -> Self {
+		if left_align == self.left_align {
+			return self.clone();
+		}
+
+		let pad = (self.words.len() as u64 * 64 - self.len) as u32;
+		let mut words = self.words.clone();
+		if self.left_align {
+			// big-endian padded value -> little-endian unpadded value
+			words.reverse();
+			shift_down(&mut words, pad);
+		} else {
+			// little-endian unpadded value -> big-endian padded value
+			shift_up(&mut words, pad);
+			words.reverse();
+		}
+
+		Self {
+			words,
+			len: self.len,
+			left_align,
+		}
+	}
+
+	/// Combines two vectors word by word, lining `bits` up against the
+	/// aligned side of `self` as `BitArray::aligned_to` does.
+	pub fn apply_binary<F>(&self, func: F, bits: &Self) -> Self
+		where F: Fn(u64, u64) -> u64
+	{
+		let bits = bits.realigned(self.left_align);
+		let mut result = Self {
+			words: self.words.iter()
+				.zip(bits.words.iter())
+				.map(|(&x, &y)| func(x, y))
+				.collect(),
+			len: u64::min(self.len, bits.len),
+			left_align: self.left_align,
+		};
+		result.clear_tail();
+		result
+	}
+
+	// Zeroes the unused offsets of the last word.
+	fn clear_tail(&mut self) {
+		if !self.len.is_multiple_of(64) {
+			let mask = self.offset_mask(self.len % 64);
+			if let Some(last) = self.words.last_mut() {
+				*last &= mask;
+			}
+		}
+	}
+
+	fn locate(&self, index: u64) -> (usize, u64) {
+		((index / 64) as usize, self.offset_bit(index % 64))
+	}
+
+	fn offset_bit(&self, offset: u64) -> u64 {
+		if self.left_align {1u64 << (63 - offset)}
+		else {1u64 << offset}
+	}
+
+	// Selects the offsets within a word that come before `offset`.
+	fn offset_mask(&self, offset: u64) -> u64 {
+		if offset == 0 {0}
+		else if self.left_align {!0u64 << (64 - offset)}
+		else {!0u64 >> (64 - offset)}
+	}
+
+	fn move_up(&self, word: u64) -> u64 {
+		if self.left_align {word >> 1} else {word << 1}
+	}
+
+	fn move_down(&self, word: u64) -> u64 {
+		if self.left_align {word << 1} else {word >> 1}
+	}
+}
+
+fn word_count(bits: u64) -> usize {
+	bits.div_ceil(64) as usize
+}
+
+// Shifts a little-endian multi-word value towards its most significant end.
+fn shift_up(words: &mut [u64], shift: u32) {
+	if shift == 0 {
+		return;
+	}
+	for i in (0..words.len()).rev() {
+		let carry = if i > 0 {words[i-1] >> (64 - shift)} else {0};
+		words[i] = (words[i] << shift) | carry;
+	}
+}
+
+// Shifts a little-endian multi-word value towards its least significant end.
+fn shift_down(words: &mut [u64], shift: u32) {
+	if shift == 0 {
+		return;
+	}
+	for i in 0..words.len() {
+		let carry = words.get(i+1).map_or(0, |&w| w << (64 - shift));
+		words[i] = (words[i] >> shift) | carry;
+	}
+}
+
+impl Extend<bool> for BitVec {
+	fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+		for value in iter {
+			self.push(value);
+		}
+	}
+}
+
+
+macro_rules! impl_binary_op {
+	($Op:ident, $op:ident, $OpAssign:ident, $op_assign:ident, $func:expr) => {
+		impl $Op for BitVec {
+			type Output = BitVec;
+
+			fn $op(self, bits: BitVec) -> BitVec {
+				self.apply_binary($func, &bits)
+			}
+		}
+
+		impl $Op<&BitVec> for &BitVec {
+			type Output = BitVec;
+
+			fn $op(self, bits: &BitVec) -> BitVec {
+				self.apply_binary($func, bits)
+			}
+		}
+
+		impl $OpAssign<&BitVec> for BitVec {
+			fn $op_assign(&mut self, bits: &BitVec) {
+				*self = self.apply_binary($func, bits);
+			}
+		}
+	};
+}
+
+impl_binary_op!(BitAnd, bitand, BitAndAssign, bitand_assign, |x, y| x & y);
+impl_binary_op!(BitOr, bitor, BitOrAssign, bitor_assign, |x, y| x | y);
+impl_binary_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, |x, y| x ^ y);
+
+impl Not for &BitVec {
+	type Output = BitVec;
+
+	fn not(self) -> BitVec {
+		let mut result = BitVec {
+			words: self.words.iter().map(|&w| !w).collect(),
+			len: self.len,
+			left_align: self.left_align,
+		};
+		result.clear_tail();
+		result
+	}
+}
+
+impl Not for BitVec {
+	type Output = BitVec;
+
+	fn not(self) -> BitVec {
+		!&self
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn from_bools(bools: &[bool], left_align: bool) -> BitVec {
+		let mut bits = BitVec::new(left_align);
+		bits.extend(bools.iter().cloned());
+		bits
+	}
+
+	fn to_bools(bits: &BitVec) -> Vec<bool> {
+		(0..bits.length()).map(|i| bits.get(i).unwrap()).collect()
+	}
+
+	#[test]
+	fn push_pop() {
+		for &left_align in &[false, true] {
+			let mut bits = BitVec::new(left_align);
+			for i in 0..150 {
+				bits.push(i % 3 == 0);
+			}
+			assert_eq!(bits.length(), 150);
+			assert_eq!(bits.words.len(), 3);
+
+			for i in (0..150).rev() {
+				assert_eq!(bits.pop(), Some(i % 3 == 0));
+			}
+			assert_eq!(bits.pop(), None);
+			assert!(bits.words.is_empty());
+		}
+	}
+
+	#[test]
+	fn from_bitarray() {
+		let bits = BitVec::from(BitArray::<u64>::new(0b1101, 4, true).unwrap());
+		assert_eq!(to_bools(&bits), vec![true, true, false, true]);
+
+		let bits = BitVec::from(BitArray::<u64>::new(0b1101, 4, false).unwrap());
+		assert_eq!(to_bools(&bits), vec![true, false, true, true]);
+	}
+
+	#[test]
+	fn insert_remove() {
+		for &left_align in &[false, true] {
+			let mut expected: Vec<bool> = (0..130).map(|i| i % 5 < 2).collect();
+			let mut bits = from_bools(&expected, left_align);
+
+			for &(index, value) in &[(0, true), (64, false), (131, true), (70, true)] {
+				bits.insert(index, value);
+				expected.insert(index as usize, value);
+				assert_eq!(to_bools(&bits), expected);
+			}
+			for &index in &[0, 63, 64, 130, 5] {
+				assert_eq!(bits.remove(index), expected.remove(index as usize));
+				assert_eq!(to_bools(&bits), expected);
+			}
+			assert_eq!(bits, from_bools(&expected, left_align));
+		}
+	}
+
+	#[test]
+	fn truncate() {
+		let mut bits = from_bools(&[true; 100], false);
+		bits.truncate(70);
+		assert_eq!(bits.length(), 70);
+		assert_eq!(bits.words, vec![!0u64, 0b111111]);
+
+		let mut bits = from_bools(&[true; 100], true);
+		bits.truncate(70);
+		assert_eq!(bits.words, vec![!0u64, 0b111111 << 58]);
+	}
+
+	#[test]
+	fn realigned() {
+		let bools: Vec<bool> = (0..100).map(|i| i % 7 == 0).collect();
+		let bits = from_bools(&bools, true).realigned(false);
+
+		let reversed: Vec<bool> = bools.iter().rev().cloned().collect();
+		assert_eq!(bits, from_bools(&reversed, false));
+		assert_eq!(bits.realigned(true), from_bools(&bools, true));
+	}
+
+	#[test]
+	fn bitwise_ops() {
+		let b1 = from_bools(&[true, true, false, false, true], true);
+		let b2 = from_bools(&[true, false, true, false], true);
+
+		assert_eq!(&b1 & &b2, from_bools(&[true, false, false, false], true));
+		assert_eq!(&b1 | &b2, from_bools(&[true, true, true, false], true));
+		assert_eq!(b1.clone() ^ b2.clone(), from_bools(&[false, true, true, false], true));
+		assert_eq!(!&b2, from_bools(&[false, true, false, true], true));
+	}
+
+	#[test]
+	fn bitwise_ops_match_bitarray() {
+		let a1 = BitArray::<u64>::new(0b110011, 6, true).unwrap();
+		let a2 = BitArray::<u64>::new(0b1010, 4, false).unwrap();
+
+		assert_eq!(
+			BitVec::from(a1) ^ BitVec::from(a2),
+			BitVec::from(a1 ^ a2),
+		);
+		assert_eq!(
+			BitVec::from(a2) & BitVec::from(a1),
+			BitVec::from(a2 & a1),
+		);
+	}
+
+	#[test]
+	fn apply_binary_clears_tail() {
+		for &left_align in &[false, true] {
+			let b1 = from_bools(&[true, false, false], left_align);
+			let b2 = from_bools(&[false, false, true], left_align);
+			let nor = b1.apply_binary(|x, y| !(x | y), &b2);
+			assert_eq!(nor, from_bools(&[false, true, false], left_align));
+			let long = from_bools(&[true; 70], left_align);
+			let not = long.apply_binary(|x, _| !x, &long);
+			assert_eq!(not, from_bools(&[false; 70], left_align));
+		}
+	}
+}
@@ -0,0 +1,207 @@
+use core::ops::Deref;
+
+use crate::bitarray::{BitArray, BitArrayError};
+use crate::bitstore::BitStore;
+
+
+/// Which end of the window is written to the first byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ByteOrder {
+	/// The most significant bits come first.
+	Big,
+	/// The least significant bits come first.
+	Little,
+}
+
+/// Which end of each byte is filled first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BitOrder {
+	/// The first bit written lands in the byte's most significant bit.
+	Msb0,
+	/// The first bit written lands in the byte's least significant bit.
+	Lsb0,
+}
+
+// Room for the widest window, a 128-bit word.
+const BUFFER_LEN: usize = 16;
+
+/// The bytes of a `BitArray`, as returned by `BitArray::to_bytes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bytes {
+	buf: [u8; BUFFER_LEN],
+	len: usize,
+}
+
+impl Deref for Bytes {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		&self.buf[..self.len]
+	}
+}
+
+impl AsRef<[u8]> for Bytes {
+	fn as_ref(&self) -> &[u8] {
+		self
+	}
+}
+
+// Big-endian bytes in MSB0 order and little-endian bytes in LSB0 order are
+// the plain integer layouts; the other two pairings mirror each byte.
+fn mirrored(order: ByteOrder, bit_order: BitOrder) -> bool {
+	(order == ByteOrder::Big) != (bit_order == BitOrder::Msb0)
+}
+
+fn byte_count(len: u64) -> usize {
+	len.div_ceil(8) as usize
+}
+
+impl<W: BitStore> BitArray<W> {
+	/// Packs the window into exactly `ceil(length() / 8)` bytes.
+	///
+	/// The padding bits are cleared and sit on the non-aligned side of the
+	/// window: below it when left-aligned, above it when right-aligned.
+	pub fn to_bytes(&self, order: ByteOrder, bit_order: BitOrder) -> Bytes {
+		let len = byte_count(self.length());
+		let padding = 8 * len as u64 - self.length();
+		let value = W::value(self).to_u128();
+		let value = if self.is_left_aligned() {value << padding} else {value};
+
+		let mut bytes = Bytes {buf: [0; BUFFER_LEN], len};
+		for (i, byte) in bytes.buf[..len].iter_mut().enumerate() {
+			let shift = match order {
+				ByteOrder::Big => len - 1 - i,
+				ByteOrder::Little => i,
+			};
+			*byte = (value >> (8 * shift)) as u8;
+			if mirrored(order, bit_order) {
+				*byte = byte.reverse_bits();
+			}
+		}
+		bytes
+	}
+
+	/// Unpacks a `len`-bit array from the layout written by `to_bytes`.
+	///
+	/// Fails if `bytes` does not hold exactly `ceil(len / 8)` bytes or if
+	/// any of the padding bits are set.
+	pub fn from_bytes(
+		bytes: &[u8],
+		len: u64,
+		order: ByteOrder,
+		bit_order: BitOrder,
+		left_align: bool,
+	) -> Result<Self, BitArrayError> {
+		if len > W::BITS {
+			return Err(BitArrayError::LengthOverflow {
+				length: len,
+				capacity: W::BITS,
+			});
+		}
+		if bytes.len() != byte_count(len) {
+			return Err(BitArrayError::ByteCount {
+				count: bytes.len(),
+				expected: byte_count(len),
+			});
+		}
+
+		let mut value = 0u128;
+		for (i, &byte) in bytes.iter().enumerate() {
+			let shift = match order {
+				ByteOrder::Big => bytes.len() - 1 - i,
+				ByteOrder::Little => i,
+			};
+			let byte =
+				if mirrored(order, bit_order) {byte.reverse_bits()}
+				else {byte};
+			value |= (byte as u128) << (8 * shift);
+		}
+
+		let padding = 8 * bytes.len() as u64 - len;
+		let mask = if len == 0 {0} else {u128::MAX >> (128 - len)};
+		let mask = if left_align {mask << padding} else {mask};
+		if value & !mask != 0 {
+			return Err(BitArrayError::BitsOutsideWindow {array: value, mask});
+		}
+
+		let value = if left_align {value >> padding} else {value};
+		W::new(W::from_u128(value), len, left_align)
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn to_bytes() {
+		use self::BitOrder::*;
+		use self::ByteOrder::*;
+
+		let bits = BitArray::<u16>::new(0b1_0110_0101, 9, true).unwrap();
+		assert_eq!(*bits.to_bytes(Big, Msb0), [0b1011_0010, 0b1000_0000]);
+		assert_eq!(*bits.to_bytes(Big, Lsb0), [0b0100_1101, 0b0000_0001]);
+		assert_eq!(*bits.to_bytes(Little, Lsb0), [0b1000_0000, 0b1011_0010]);
+		assert_eq!(*bits.to_bytes(Little, Msb0), [0b0000_0001, 0b0100_1101]);
+
+		let bits = BitArray::<u16>::new(0b1_0110_0101, 9, false).unwrap();
+		assert_eq!(*bits.to_bytes(Big, Msb0), [0b0000_0001, 0b0110_0101]);
+		assert_eq!(*bits.to_bytes(Little, Lsb0), [0b0110_0101, 0b0000_0001]);
+
+		let empty = BitArray::<u8>::zeros(0, true).unwrap();
+		assert!(empty.to_bytes(Big, Msb0).is_empty());
+		assert_eq!(BitArray::from_bytes(&[], 0, Big, Msb0, true), Ok(empty));
+
+		let wide = BitArray::<u128>::ones(128, false).unwrap();
+		assert_eq!(wide.to_bytes(Little, Lsb0).len(), 16);
+	}
+
+	#[test]
+	fn bytes_roundtrip() {
+		let orders = [ByteOrder::Big, ByteOrder::Little];
+		let bit_orders = [BitOrder::Msb0, BitOrder::Lsb0];
+		for len in 1..=32 {
+			for &left_align in &[true, false] {
+				let value = 0x9e37_79b9 >> (32 - len);
+				let bits = BitArray::<u32>::new(value, len, left_align).unwrap();
+				for &order in &orders {
+					for &bit_order in &bit_orders {
+						let bytes = bits.to_bytes(order, bit_order);
+						assert_eq!(bytes.len() as u64, len.div_ceil(8));
+						let parsed = BitArray::from_bytes(
+							&bytes, len, order, bit_order, left_align,
+						);
+						assert_eq!(parsed, Ok(bits));
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn from_bytes_errors() {
+		let from_bytes = |bytes: &[u8], len, left_align| {
+			BitArray::<u16>::from_bytes(
+				bytes, len, ByteOrder::Big, BitOrder::Msb0, left_align,
+			)
+		};
+		assert_eq!(
+			from_bytes(&[0xff], 9, true),
+			Err(BitArrayError::ByteCount {count: 1, expected: 2}),
+		);
+		assert_eq!(
+			from_bytes(&[0; 3], 17, true),
+			Err(BitArrayError::LengthOverflow {length: 17, capacity: 16}),
+		);
+		assert_eq!(
+			from_bytes(&[0b1000_0001], 4, true),
+			Err(BitArrayError::BitsOutsideWindow {
+				array: 0b1000_0001,
+				mask: 0b1111_0000,
+			}),
+		);
+		assert!(from_bytes(&[0b1000_0001], 4, false).is_err());
+		assert!(from_bytes(&[0b0000_0001], 4, false).is_ok());
+	}
+}
@@ -0,0 +1,135 @@
+use core::fmt;
+use core::str;
+
+use crate::bitarray::BitArray;
+use crate::bitstore::BitStore;
+
+
+// Room for a 128-bit window in binary plus a two-character prefix.
+const BUFFER_LEN: usize = 130;
+
+// Writes the window into the end of `buf` as base-2^`shift` digits, zero
+// padded to cover every bit of the window, and returns the written text.
+fn write_digits<'a, W: BitStore>(
+	bits: &BitArray<W>,
+	shift: u64,
+	upper: bool,
+	buf: &'a mut [u8; BUFFER_LEN],
+) -> &'a str {
+	let symbols: &[u8; 16] =
+		if upper {b"0123456789ABCDEF"}
+		else {b"0123456789abcdef"};
+	let count = bits.length().div_ceil(shift) as usize;
+	let value = if count == 0 {0} else {W::value(bits).to_u128()};
+
+	let start = BUFFER_LEN - count;
+	for i in 0..count {
+		let digit = (value >> (i as u64 * shift)) & ((1 << shift) - 1);
+		buf[BUFFER_LEN - 1 - i] = symbols[digit as usize];
+	}
+	str::from_utf8(&buf[start..]).unwrap()
+}
+
+/// Formats the window as `0b` followed by exactly `length()` binary digits.
+impl<W: BitStore> fmt::Display for BitArray<W> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut buf = [0u8; BUFFER_LEN];
+		let len = write_digits(self, 1, false, &mut buf).len();
+		let start = BUFFER_LEN - len - 2;
+		buf[start..start+2].copy_from_slice(b"0b");
+		f.pad(str::from_utf8(&buf[start..]).unwrap())
+	}
+}
+
+impl<W: BitStore> fmt::Binary for BitArray<W> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut buf = [0u8; BUFFER_LEN];
+		f.pad_integral(true, "0b", write_digits(self, 1, false, &mut buf))
+	}
+}
+
+impl<W: BitStore> fmt::Octal for BitArray<W> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut buf = [0u8; BUFFER_LEN];
+		f.pad_integral(true, "0o", write_digits(self, 3, false, &mut buf))
+	}
+}
+
+impl<W: BitStore> fmt::LowerHex for BitArray<W> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut buf = [0u8; BUFFER_LEN];
+		f.pad_integral(true, "0x", write_digits(self, 4, false, &mut buf))
+	}
+}
+
+impl<W: BitStore> fmt::UpperHex for BitArray<W> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut buf = [0u8; BUFFER_LEN];
+		f.pad_integral(true, "0x", write_digits(self, 4, true, &mut buf))
+	}
+}
+
+/// Lays out the whole word, with a `.` for each margin bit and the window
+/// in brackets, e.g. `BitArray(..[1011]...., left_align)`.
+impl<W: BitStore> fmt::Debug for BitArray<W> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut buf = [0u8; BUFFER_LEN];
+		f.write_str("BitArray(")?;
+		for _ in 0..self.left_margin() {
+			f.write_str(".")?;
+		}
+		write!(f, "[{}]", write_digits(self, 1, false, &mut buf))?;
+		for _ in 0..self.right_margin() {
+			f.write_str(".")?;
+		}
+		f.write_str(
+			if self.is_left_aligned() {", left_align)"}
+			else {", right_align)"}
+		)
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn display() {
+		let bits = BitArray::<u8>::new(0b0011, 4, false).unwrap();
+		assert_eq!(bits.to_string(), "0b0011");
+		assert_eq!(format!("{:>8}", bits), "  0b0011");
+		assert_eq!(format!("{:*<7}", bits), "0b0011*");
+
+		let wide = BitArray::<u128>::ones(128, true).unwrap();
+		assert_eq!(wide.to_string().len(), 130);
+
+		let empty = BitArray::<u8>::zeros(0, true).unwrap();
+		assert_eq!(empty.to_string(), "0b");
+		assert_eq!(format!("{:#x}", empty), "0x");
+	}
+
+	#[test]
+	fn radix_formats() {
+		let bits = BitArray::<u16>::new(0b0_1010_1111, 9, true).unwrap();
+		assert_eq!(format!("{:b}", bits), "010101111");
+		assert_eq!(format!("{:#b}", bits), "0b010101111");
+		assert_eq!(format!("{:#014b}", bits), "0b000010101111");
+		assert_eq!(format!("{:o}", bits), "257");
+		assert_eq!(format!("{:x}", bits), "0af");
+		assert_eq!(format!("{:#X}", bits), "0x0AF");
+		assert_eq!(format!("{:>5x}", bits), "  0af");
+	}
+
+	#[test]
+	fn debug() {
+		let bits = BitArray::<u8>::from_margins(0b0001_1000, 2, 3, true).unwrap();
+		assert_eq!(format!("{:?}", bits), "BitArray(..[011]..., left_align)");
+
+		let bits = BitArray::<u8>::new(0b1011, 4, false).unwrap();
+		assert_eq!(format!("{:?}", bits), "BitArray(....[1011], right_align)");
+
+		let empty = BitArray::<u8>::zeros(0, false).unwrap();
+		assert_eq!(format!("{:?}", empty), "BitArray(........[], right_align)");
+	}
+}
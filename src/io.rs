@@ -0,0 +1,540 @@
+use core::cmp;
+use core::error::Error;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+use crate::bitarray::BitArray;
+use crate::bitstore::BitStore;
+use crate::bytes::BitOrder;
+
+
+#[derive(Debug)]
+pub enum ReadError {
+	/// The stream ran out `available` bits into a `requested`-bit read.
+	UnexpectedEof { requested: u64, available: u64 },
+	/// The underlying reader failed.
+	#[cfg(feature = "std")]
+	Io(io::Error),
+}
+
+impl fmt::Display for ReadError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ReadError::UnexpectedEof { requested, available } =>
+				write!(f, "expected {} bits, but the stream ends after {}",
+					requested, available),
+			#[cfg(feature = "std")]
+			ReadError::Io(err) => write!(f, "{}", err),
+		}
+	}
+}
+
+impl Error for ReadError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			#[cfg(feature = "std")]
+			ReadError::Io(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+
+// Reads `len` bits from `bytes`, starting `offset` bits in. The first bit
+// read ends up most significant for MSB-first order and least significant
+// for LSB-first order.
+fn extract(bytes: &[u8], offset: u64, len: u64, order: BitOrder) -> u128 {
+	let mut value = 0u128;
+	let mut done = 0;
+	while done < len {
+		let position = offset + done;
+		let byte = bytes[(position / 8) as usize] as u128;
+		let skip = position % 8;
+		let take = cmp::min(8 - skip, len - done);
+		let mask = (1 << take) - 1;
+		match order {
+			BitOrder::Msb0 =>
+				value = (value << take) | ((byte >> (8 - skip - take)) & mask),
+			BitOrder::Lsb0 =>
+				value |= ((byte >> skip) & mask) << done,
+		}
+		done += take;
+	}
+	value
+}
+
+// Wraps the bits read into an array whose index order matches the stream:
+// left-aligned for MSB-first order and right-aligned for LSB-first order.
+fn to_array<W: BitStore>(value: u128, len: u64, order: BitOrder)
+	-> BitArray<W>
+{
+	W::new(W::from_u128(value), len, order == BitOrder::Msb0)
+		.expect("the bits read fit within the word")
+}
+
+fn check_width<W: BitStore>(len: u64) {
+	assert!(len <= W::BITS, "cannot read {} bits into a {}-bit word",
+		len, W::BITS);
+}
+
+
+/// Reads bit fields of any width from a byte slice.
+#[derive(Debug, Clone)]
+pub struct SliceReader<'a> {
+	bytes: &'a [u8],
+	position: u64,
+	order: BitOrder,
+}
+
+impl<'a> SliceReader<'a> {
+	/// Reads from the start of `bytes`, taking the bits of each byte in
+	/// `order`.
+	pub fn new(bytes: &'a [u8], order: BitOrder) -> Self {
+		Self {bytes, position: 0, order}
+	}
+
+	/// Returns the number of bits read or skipped so far.
+	pub fn position(&self) -> u64 {
+		self.position
+	}
+
+	/// Returns the number of bits left to read.
+	pub fn remaining(&self) -> u64 {
+		8 * self.bytes.len() as u64 - self.position
+	}
+
+	/// Reads the next `len` bits, the first one landing at index 0.
+	///
+	/// On failure nothing is consumed. Panics if `len` exceeds the bits in
+	/// the word.
+	pub fn read_bits<W: BitStore>(&mut self, len: u64)
+		-> Result<BitArray<W>, ReadError>
+	{
+		let bits = self.peek_bits(len)?;
+		self.position += len;
+		Ok(bits)
+	}
+
+	/// Reads the next `len` bits without consuming them.
+	pub fn peek_bits<W: BitStore>(&self, len: u64)
+		-> Result<BitArray<W>, ReadError>
+	{
+		check_width::<W>(len);
+		self.check_remaining(len)?;
+		let value = extract(self.bytes, self.position, len, self.order);
+		Ok(to_array(value, len, self.order))
+	}
+
+	pub fn read_bool(&mut self) -> Result<bool, ReadError> {
+		Ok(self.read_bits::<u8>(1)?.any())
+	}
+
+	/// Skips `len` bits. If fewer remain, everything up to the end is
+	/// skipped, and the error reports how many bits that was.
+	pub fn skip(&mut self, len: u64) -> Result<(), ReadError> {
+		let result = self.check_remaining(len);
+		self.position += cmp::min(len, self.remaining());
+		result
+	}
+
+	/// Skips to the start of the next byte, unless already there.
+	pub fn align_to_byte(&mut self) {
+		self.position = self.position.div_ceil(8) * 8;
+	}
+
+	fn check_remaining(&self, len: u64) -> Result<(), ReadError> {
+		if len > self.remaining() {
+			return Err(ReadError::UnexpectedEof {
+				requested: len,
+				available: self.remaining(),
+			});
+		}
+		Ok(())
+	}
+}
+
+
+// Enough bytes for a 128-bit read starting partway into a byte.
+#[cfg(feature = "std")]
+const BUFFER_LEN: usize = 17;
+
+/// Reads bit fields of any width from a byte stream.
+///
+/// Only the bytes needed to satisfy each read are pulled from the stream.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct BitReader<R: Read> {
+	reader: R,
+	buf: [u8; BUFFER_LEN],
+	len: usize,
+	// The bits already consumed from `buf[0]`.
+	offset: u64,
+	order: BitOrder,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> BitReader<R> {
+	/// Reads from `reader`, taking the bits of each byte in `order`.
+	pub fn new(reader: R, order: BitOrder) -> Self {
+		Self {reader, buf: [0; BUFFER_LEN], len: 0, offset: 0, order}
+	}
+
+	/// Returns the underlying reader; any buffered bits are lost.
+	pub fn into_inner(self) -> R {
+		self.reader
+	}
+
+	/// Reads the next `len` bits, the first one landing at index 0.
+	///
+	/// If the stream ends first, the bits it did hold stay buffered for the
+	/// next read. Panics if `len` exceeds the bits in the word.
+	pub fn read_bits<W: BitStore>(&mut self, len: u64)
+		-> Result<BitArray<W>, ReadError>
+	{
+		let bits = self.peek_bits(len)?;
+		self.consume(len);
+		Ok(bits)
+	}
+
+	/// Reads the next `len` bits without consuming them.
+	pub fn peek_bits<W: BitStore>(&mut self, len: u64)
+		-> Result<BitArray<W>, ReadError>
+	{
+		check_width::<W>(len);
+		self.fill(len)?;
+		let value = extract(&self.buf, self.offset, len, self.order);
+		Ok(to_array(value, len, self.order))
+	}
+
+	pub fn read_bool(&mut self) -> Result<bool, ReadError> {
+		Ok(self.read_bits::<u8>(1)?.any())
+	}
+
+	/// Skips `len` bits. If the stream ends first, everything up to its end
+	/// is skipped, and the error reports how many bits that was.
+	pub fn skip(&mut self, len: u64) -> Result<(), ReadError> {
+		let mut skipped = 0;
+		while skipped < len {
+			let step = cmp::min(len - skipped, 128);
+			match self.fill(step) {
+				Ok(()) => {},
+				Err(ReadError::UnexpectedEof { available, .. }) => {
+					self.consume(available);
+					return Err(ReadError::UnexpectedEof {
+						requested: len,
+						available: skipped + available,
+					});
+				},
+				Err(err) => return Err(err),
+			}
+			self.consume(step);
+			skipped += step;
+		}
+		Ok(())
+	}
+
+	/// Skips to the start of the next byte, unless already there.
+	pub fn align_to_byte(&mut self) {
+		if self.offset != 0 {
+			self.consume(8 - self.offset);
+		}
+	}
+
+	fn buffered(&self) -> u64 {
+		8 * self.len as u64 - self.offset
+	}
+
+	// Pulls in just enough bytes to hold `len` unread bits.
+	fn fill(&mut self, len: u64) -> Result<(), ReadError> {
+		let needed = (self.offset + len).div_ceil(8) as usize;
+		while self.len < needed {
+			match self.reader.read(&mut self.buf[self.len..needed]) {
+				Ok(0) => return Err(ReadError::UnexpectedEof {
+					requested: len,
+					available: self.buffered(),
+				}),
+				Ok(count) => self.len += count,
+				Err(err) if err.kind() == io::ErrorKind::Interrupted => {},
+				Err(err) => return Err(ReadError::Io(err)),
+			}
+		}
+		Ok(())
+	}
+
+	fn consume(&mut self, len: u64) {
+		let position = self.offset + len;
+		let bytes = (position / 8) as usize;
+		self.buf.copy_within(bytes..self.len, 0);
+		self.len -= bytes;
+		self.offset = position % 8;
+	}
+}
+
+
+
+/// Packs bit fields of any width into a byte stream.
+///
+/// Whole bytes go to the stream as soon as they fill; a partly filled byte
+/// waits for more bits or for `pad_to_byte`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct BitWriter<T: Write> {
+	writer: T,
+	byte: u8,
+	// The bits already written into `byte`.
+	filled: u64,
+	order: BitOrder,
+}
+
+#[cfg(feature = "std")]
+impl<T: Write> BitWriter<T> {
+	/// Writes to `writer`, filling each byte in `order`.
+	pub fn new(writer: T, order: BitOrder) -> Self {
+		Self {writer, byte: 0, filled: 0, order}
+	}
+
+	/// Returns the underlying writer; a partly filled byte is lost.
+	pub fn into_inner(self) -> T {
+		self.writer
+	}
+
+	/// Appends the value of `bits`, most significant bit first in MSB-first
+	/// order and least significant bit first otherwise.
+	///
+	/// Either way an array aligned to match the order, as `BitReader`
+	/// returns them, is written in index order.
+	pub fn write_bits<W: BitStore>(&mut self, bits: BitArray<W>)
+		-> io::Result<()>
+	{
+		let value = W::value(&bits).to_u128();
+		let len = bits.length();
+		let mut done = 0;
+		while done < len {
+			let take = cmp::min(8 - self.filled, len - done);
+			let mask = (1 << take) - 1;
+			let chunk = match self.order {
+				BitOrder::Msb0 =>
+					((value >> (len - done - take)) & mask)
+						<< (8 - self.filled - take),
+				BitOrder::Lsb0 =>
+					((value >> done) & mask) << self.filled,
+			};
+			self.byte |= chunk as u8;
+			self.filled += take;
+			done += take;
+			if self.filled == 8 {
+				self.emit()?;
+			}
+		}
+		Ok(())
+	}
+
+	pub fn write_bool(&mut self, value: bool) -> io::Result<()> {
+		let bits = BitArray::<u8>::new(value as u8, 1, true)
+			.expect("a single bit fits within the word");
+		self.write_bits(bits)
+	}
+
+	/// Fills the rest of a partly filled byte with zeros and writes it out.
+	pub fn pad_to_byte(&mut self) -> io::Result<()> {
+		if self.filled != 0 {
+			self.emit()?;
+		}
+		Ok(())
+	}
+
+	/// Flushes the underlying writer. A partly filled byte stays buffered.
+	pub fn flush(&mut self) -> io::Result<()> {
+		self.writer.flush()
+	}
+
+	fn emit(&mut self) -> io::Result<()> {
+		self.writer.write_all(&[self.byte])?;
+		self.byte = 0;
+		self.filled = 0;
+		Ok(())
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const BYTES: [u8; 4] = [0b1011_0010, 0b0110_1111, 0b0000_0001, 0xff];
+
+	#[test]
+	fn slice_reader() {
+		let mut reader = SliceReader::new(&BYTES, BitOrder::Msb0);
+		let bits = reader.read_bits::<u8>(3).unwrap();
+		assert!(bits.is_left_aligned());
+		assert_eq!(u8::from(bits), 0b101);
+		assert_eq!(u16::from(reader.peek_bits::<u16>(13).unwrap()), 0x126f);
+		assert_eq!(u16::from(reader.read_bits::<u16>(13).unwrap()), 0x126f);
+		assert_eq!(reader.position(), 16);
+		assert!(!reader.read_bool().unwrap());
+
+		reader.align_to_byte();
+		assert_eq!(reader.position(), 24);
+		reader.align_to_byte();
+		assert_eq!(reader.position(), 24);
+		reader.skip(4).unwrap();
+		assert_eq!(u8::from(reader.read_bits::<u8>(4).unwrap()), 0xf);
+		assert_eq!(reader.remaining(), 0);
+
+		let mut reader = SliceReader::new(&BYTES, BitOrder::Lsb0);
+		let bits = reader.read_bits::<u8>(3).unwrap();
+		assert!(!bits.is_left_aligned());
+		assert_eq!(u8::from(bits), 0b010);
+		assert_eq!(bits.iter().collect::<Vec<_>>(), vec![false, true, false]);
+		assert_eq!(u16::from(reader.read_bits::<u16>(9).unwrap()), 0b1_1111_0110);
+	}
+
+	#[test]
+	fn slice_reader_eof() {
+		let mut reader = SliceReader::new(&BYTES, BitOrder::Msb0);
+		reader.skip(20).unwrap();
+		assert!(matches!(
+			reader.read_bits::<u16>(13),
+			Err(ReadError::UnexpectedEof {requested: 13, available: 12}),
+		));
+		assert_eq!(reader.position(), 20);
+		assert_eq!(
+			reader.peek_bits::<u16>(12).unwrap(),
+			BitArray::<u16>::new(0x1ff, 12, true).unwrap(),
+		);
+		assert!(matches!(
+			reader.skip(13),
+			Err(ReadError::UnexpectedEof {requested: 13, available: 12}),
+		));
+		assert_eq!(reader.position(), 32);
+		assert_eq!(
+			reader.read_bool().unwrap_err().to_string(),
+			"expected 1 bits, but the stream ends after 0",
+		);
+	}
+
+	#[test]
+	#[should_panic(expected = "cannot read 9 bits into a 8-bit word")]
+	fn read_too_wide() {
+		let _ = SliceReader::new(&BYTES, BitOrder::Msb0).read_bits::<u8>(9);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn bit_reader_skip_past_end() {
+		let mut reader = BitReader::new(&BYTES[..3], BitOrder::Msb0);
+		reader.read_bits::<u8>(2).unwrap();
+		assert!(matches!(
+			reader.skip(30),
+			Err(ReadError::UnexpectedEof {requested: 30, available: 22}),
+		));
+		assert!(matches!(
+			reader.read_bits::<u8>(8),
+			Err(ReadError::UnexpectedEof {requested: 8, available: 0}),
+		));
+		assert!(reader.read_bool().is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn bit_reader_matches_slice_reader() {
+		let bytes: Vec<u8> = (0..40u8).map(|i| i.wrapping_mul(0x9d)).collect();
+		for &order in &[BitOrder::Msb0, BitOrder::Lsb0] {
+			let mut slice = SliceReader::new(&bytes, order);
+			let mut stream = BitReader::new(&bytes[..], order);
+			for &len in &[3, 13, 47, 0, 1, 128, 7, 64, 5] {
+				assert_eq!(
+					stream.peek_bits::<u128>(len).unwrap(),
+					slice.peek_bits::<u128>(len).unwrap(),
+				);
+				assert_eq!(
+					stream.read_bits::<u128>(len).unwrap(),
+					slice.read_bits::<u128>(len).unwrap(),
+				);
+			}
+			stream.align_to_byte();
+			slice.align_to_byte();
+			stream.skip(9).unwrap();
+			slice.skip(9).unwrap();
+			assert_eq!(stream.read_bool().unwrap(), slice.read_bool().unwrap());
+
+			// A skip past the end stops both readers at the end.
+			let remaining = slice.remaining();
+			let results = [stream.skip(remaining + 1), slice.skip(remaining + 1)];
+			for result in results {
+				assert!(matches!(
+					result,
+					Err(ReadError::UnexpectedEof {available, ..})
+						if available == remaining,
+				));
+			}
+			assert_eq!(slice.position(), 8 * bytes.len() as u64);
+			assert!(matches!(
+				stream.read_bool(),
+				Err(ReadError::UnexpectedEof {available: 0, ..}),
+			));
+			assert!(slice.read_bool().is_err());
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn bit_writer() {
+		let mut writer = BitWriter::new(Vec::new(), BitOrder::Msb0);
+		writer.write_bits(BitArray::<u8>::new(0b101, 3, true).unwrap()).unwrap();
+		writer.write_bits(BitArray::<u16>::new(0x126f, 13, false).unwrap())
+			.unwrap();
+		writer.write_bool(false).unwrap();
+		writer.write_bits(BitArray::<u8>::new(1, 7, false).unwrap()).unwrap();
+		writer.pad_to_byte().unwrap();
+		writer.write_bits(BitArray::<u8>::new(0xf, 4, true).unwrap()).unwrap();
+		writer.write_bits(BitArray::<u8>::new(0xf, 4, true).unwrap()).unwrap();
+		writer.flush().unwrap();
+		assert_eq!(writer.into_inner(), BYTES);
+
+		let mut writer = BitWriter::new(Vec::new(), BitOrder::Lsb0);
+		writer.write_bits(BitArray::<u8>::new(0b010, 3, false).unwrap()).unwrap();
+		writer.write_bits(BitArray::<u16>::new(0b1_1111_0110, 9, true).unwrap())
+			.unwrap();
+		writer.write_bool(false).unwrap();
+		writer.write_bool(true).unwrap();
+		assert_eq!(writer.into_inner(), [0b1011_0010]);
+
+		let mut writer = BitWriter::new(Vec::new(), BitOrder::Lsb0);
+		writer.write_bits(BitArray::<u8>::new(0b10_0010, 6, false).unwrap())
+			.unwrap();
+		writer.pad_to_byte().unwrap();
+		assert_eq!(writer.into_inner(), [0b0010_0010]);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn bit_writer_roundtrip() {
+		let widths: [u64; 9] = [3, 13, 47, 0, 1, 128, 7, 64, 5];
+		for &order in &[BitOrder::Msb0, BitOrder::Lsb0] {
+			let mut writer = BitWriter::new(Vec::new(), order);
+			let fields: Vec<BitArray<u128>> = widths.iter()
+				.map(|&len| BitArray::<u128>::new(
+					0x0123_4567_89ab_cdef_fedc_ba98_7654_3210u128
+						.checked_shr(128 - len as u32)
+						.unwrap_or(0),
+					len,
+					order == BitOrder::Msb0,
+				).unwrap())
+				.collect();
+			for &field in &fields {
+				writer.write_bits(field).unwrap();
+			}
+			writer.pad_to_byte().unwrap();
+			let bytes = writer.into_inner();
+			assert_eq!(bytes.len() as u64, widths.iter().sum::<u64>().div_ceil(8));
+
+			let mut reader = BitReader::new(&bytes[..], order);
+			for (&len, &field) in widths.iter().zip(&fields) {
+				assert_eq!(reader.read_bits::<u128>(len).unwrap(), field);
+			}
+		}
+	}
+}
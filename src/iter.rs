@@ -0,0 +1,287 @@
+use core::iter::{DoubleEndedIterator, ExactSizeIterator, FromIterator};
+
+use crate::bitarray::{BitArray, BitArrayError};
+use crate::bitstore::BitStore;
+
+
+/// Iterates over the bits of a `BitArray`, starting from the aligned side.
+#[derive(Debug, Clone)]
+pub struct Iter<W: BitStore> {
+	bits: BitArray<W>,
+	front: u64,
+	back: u64,
+}
+
+impl<W: BitStore> Iterator for Iter<W> {
+	type Item = bool;
+
+	fn next(&mut self) -> Option<bool> {
+		if self.front == self.back {
+			return None;
+		}
+		self.front += 1;
+		self.bits.get(self.front - 1)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let len = (self.back - self.front) as usize;
+		(len, Some(len))
+	}
+}
+
+impl<W: BitStore> DoubleEndedIterator for Iter<W> {
+	fn next_back(&mut self) -> Option<bool> {
+		if self.front == self.back {
+			return None;
+		}
+		self.back -= 1;
+		self.bits.get(self.back)
+	}
+}
+
+impl<W: BitStore> ExactSizeIterator for Iter<W> {}
+
+
+/// Iterates over the indices of the set bits in a word, as returned by
+/// `BitArray::iter_ones` and `BitArray::iter_zeros`.
+#[derive(Debug, Clone)]
+pub struct Indices<W: BitStore> {
+	// The window value; index `i` is bit `i` when right-aligned and bit
+	// `len-1-i` when left-aligned.
+	value: W,
+	len: u64,
+	left_align: bool,
+}
+
+impl<W: BitStore> Indices<W> {
+	fn pop_lowest(&mut self) -> u64 {
+		let position = self.value.trailing_zeros();
+		self.value = self.value & !(W::ONE << position);
+		position
+	}
+
+	fn pop_highest(&mut self) -> u64 {
+		let position = W::BITS - 1 - self.value.leading_zeros();
+		self.value = self.value & !(W::ONE << position);
+		position
+	}
+}
+
+impl<W: BitStore> Iterator for Indices<W> {
+	type Item = u64;
+
+	fn next(&mut self) -> Option<u64> {
+		if self.value == W::ZERO {
+			return None;
+		}
+		Some(
+			if self.left_align {self.len - 1 - self.pop_highest()}
+			else {self.pop_lowest()}
+		)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let len = self.value.count_ones() as usize;
+		(len, Some(len))
+	}
+}
+
+impl<W: BitStore> DoubleEndedIterator for Indices<W> {
+	fn next_back(&mut self) -> Option<u64> {
+		if self.value == W::ZERO {
+			return None;
+		}
+		Some(
+			if self.left_align {self.len - 1 - self.pop_lowest()}
+			else {self.pop_highest()}
+		)
+	}
+}
+
+impl<W: BitStore> ExactSizeIterator for Indices<W> {}
+
+
+/// Iterates over consecutive slices of a `BitArray`, starting from the
+/// aligned side; the last slice may be shorter.
+#[derive(Debug, Clone)]
+pub struct Chunks<W: BitStore> {
+	bits: BitArray<W>,
+	size: u64,
+	front: u64,
+}
+
+impl<W: BitStore> Iterator for Chunks<W> {
+	type Item = BitArray<W>;
+
+	fn next(&mut self) -> Option<BitArray<W>> {
+		if self.front == self.bits.length() {
+			return None;
+		}
+		let end = u64::min(self.front + self.size, self.bits.length());
+		let chunk = self.bits.slice(self.front..end);
+		self.front = end;
+		Some(chunk)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let len = (self.bits.length() - self.front).div_ceil(self.size) as usize;
+		(len, Some(len))
+	}
+}
+
+impl<W: BitStore> ExactSizeIterator for Chunks<W> {}
+
+
+impl<W: BitStore> BitArray<W> {
+	pub fn iter(&self) -> Iter<W> {
+		Iter {
+			bits: *self,
+			front: 0,
+			back: self.length(),
+		}
+	}
+
+	/// Iterates over the indices of the set bits.
+	pub fn iter_ones(&self) -> Indices<W> {
+		Indices {
+			value: W::value(self),
+			len: self.length(),
+			left_align: self.is_left_aligned(),
+		}
+	}
+
+	/// Iterates over the indices of the cleared bits.
+	pub fn iter_zeros(&self) -> Indices<W> {
+		(!*self).iter_ones()
+	}
+
+	/// Iterates over `size`-bit slices. Panics if `size` is zero.
+	pub fn chunks(&self, size: u64) -> Chunks<W> {
+		assert!(size != 0, "chunk size must be non-zero");
+		Chunks {
+			bits: *self,
+			size,
+			front: 0,
+		}
+	}
+
+	/// Collects bits into an array, the first bit landing on the aligned
+	/// side.
+	pub fn try_from_iter<I>(iter: I, left_align: bool)
+		-> Result<Self, BitArrayError>
+		where I: IntoIterator<Item = bool>
+	{
+		let mut bits = W::new(W::ZERO, W::BITS, left_align)?;
+		let mut len = 0;
+		for value in iter {
+			if len == W::BITS {
+				return Err(BitArrayError::LengthOverflow {
+					length: len + 1,
+					capacity: W::BITS,
+				});
+			}
+			bits.set(len, value);
+			len += 1;
+		}
+		Ok(W::trim_to(bits, len))
+	}
+}
+
+impl<W: BitStore> IntoIterator for BitArray<W> {
+	type Item = bool;
+	type IntoIter = Iter<W>;
+
+	fn into_iter(self) -> Iter<W> {
+		self.iter()
+	}
+}
+
+impl<W: BitStore> IntoIterator for &BitArray<W> {
+	type Item = bool;
+	type IntoIter = Iter<W>;
+
+	fn into_iter(self) -> Iter<W> {
+		self.iter()
+	}
+}
+
+/// Collects into a left-aligned array, so the bits read in iteration order
+/// from the most significant end.
+///
+/// Panics if the iterator yields more bits than the word holds; use
+/// `BitArray::try_from_iter` to handle that case.
+impl<W: BitStore> FromIterator<bool> for BitArray<W> {
+	fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+		match Self::try_from_iter(iter, true) {
+			Ok(bits) => bits,
+			Err(err) => panic!("{}", err),
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn iter() {
+		let bits = BitArray::<u8>::new(0b1101, 4, false).unwrap();
+		assert_eq!(bits.iter().len(), 4);
+		assert_eq!(
+			bits.iter().collect::<Vec<_>>(),
+			vec![true, false, true, true],
+		);
+		assert_eq!(
+			bits.iter().rev().collect::<Vec<_>>(),
+			vec![true, true, false, true],
+		);
+
+		let bits = BitArray::<u8>::new(0b1101, 4, true).unwrap();
+		assert_eq!(
+			bits.into_iter().collect::<Vec<_>>(),
+			vec![true, true, false, true],
+		);
+	}
+
+	#[test]
+	fn iter_ones_zeros() {
+		let bits = BitArray::<u16>::new(0b1_0000_1100, 9, false).unwrap();
+		assert_eq!(bits.iter_ones().len(), 3);
+		assert_eq!(bits.iter_ones().collect::<Vec<_>>(), vec![2, 3, 8]);
+		assert_eq!(bits.iter_ones().rev().collect::<Vec<_>>(), vec![8, 3, 2]);
+		assert_eq!(bits.iter_zeros().collect::<Vec<_>>(), vec![0, 1, 4, 5, 6, 7]);
+
+		let bits = BitArray::<u16>::new(0b1_0000_1100, 9, true).unwrap();
+		assert_eq!(bits.iter_ones().collect::<Vec<_>>(), vec![0, 5, 6]);
+		assert_eq!(bits.iter_zeros().rev().collect::<Vec<_>>(), vec![8, 7, 4, 3, 2, 1]);
+	}
+
+	#[test]
+	fn from_iter() {
+		let bits: BitArray<u8> = vec![true, false, true, true].into_iter().collect();
+		assert_eq!(bits, BitArray::<u8>::new(0b1011, 4, true).unwrap());
+		assert_eq!(bits.iter().collect::<BitArray<u8>>(), bits);
+
+		assert_eq!(
+			BitArray::<u8>::try_from_iter(vec![true; 9], false),
+			Err(BitArrayError::LengthOverflow {length: 9, capacity: 8}),
+		);
+		assert_eq!(
+			BitArray::<u8>::try_from_iter(vec![true; 8], false),
+			BitArray::<u8>::ones(8, false),
+		);
+	}
+
+	#[test]
+	fn chunks() {
+		let bits = BitArray::<u16>::new(0b10_1100_1011, 10, true).unwrap();
+		let chunks: Vec<u16> = bits.chunks(4).map(u16::from).collect();
+		assert_eq!(chunks, vec![0b1011, 0b0010, 0b11]);
+		assert_eq!(bits.chunks(4).len(), 3);
+
+		let bits = BitArray::<u16>::new(0b10_1100_1011, 10, false).unwrap();
+		let chunks: Vec<u16> = bits.chunks(4).map(u16::from).collect();
+		assert_eq!(chunks, vec![0b1011, 0b1100, 0b10]);
+	}
+}
@@ -1,4 +1,25 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 pub mod bitarray;
+pub mod bitarrayn;
+pub mod bitset;
+pub mod bitstore;
+#[cfg(feature = "alloc")]
+pub mod bitvec;
+pub mod bytes;
+pub mod format;
+pub mod io;
+pub mod iter;
+pub mod parse;
+#[cfg(feature = "alloc")]
+pub mod roaring;
+pub mod slice;
+
+#[cfg(feature = "serde")]
+mod serde_impl;
 
 #[cfg(test)]
 mod tests {
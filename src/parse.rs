@@ -0,0 +1,177 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::bitarray::BitArray;
+use crate::bitstore::BitStore;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+	/// The literal does not start with `0b`, `0o` or `0x`.
+	MissingPrefix,
+	/// The literal has `_` separators but no digits after its prefix.
+	NoDigits,
+	/// A character is not a digit of the literal's radix.
+	InvalidDigit(char),
+	/// The text after `:` is not `L` or `R`.
+	InvalidSuffix,
+	/// The digits hold more bits than the storage word.
+	TooLong { capacity: u64 },
+}
+
+/// An error from parsing a `BitArray` literal, pointing at the byte offset
+/// where parsing failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseBitArrayError {
+	kind: ParseErrorKind,
+	position: usize,
+}
+
+impl ParseBitArrayError {
+	pub fn kind(&self) -> ParseErrorKind {
+		self.kind
+	}
+
+	pub fn position(&self) -> usize {
+		self.position
+	}
+}
+
+impl fmt::Display for ParseBitArrayError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.kind {
+			ParseErrorKind::MissingPrefix =>
+				write!(f, "expected a 0b, 0o or 0x prefix"),
+			ParseErrorKind::NoDigits =>
+				write!(f, "expected digits at position {}", self.position),
+			ParseErrorKind::InvalidDigit(c) =>
+				write!(f, "invalid digit {:?} at position {}",
+					c, self.position),
+			ParseErrorKind::InvalidSuffix =>
+				write!(f, "expected an alignment of :L or :R at position {}",
+					self.position),
+			ParseErrorKind::TooLong { capacity } =>
+				write!(f, "digits from position {} exceed the {}-bit capacity",
+					self.position, capacity),
+		}
+	}
+}
+
+impl Error for ParseBitArrayError {}
+
+/// Parses a `0b`, `0o` or `0x` literal, optionally broken up with `_`, into
+/// an array with one bit per binary digit, three per octal digit and four
+/// per hexadecimal digit, leading zeros included.
+///
+/// A trailing `:L` or `:R` picks left or right alignment; without one the
+/// array is left-aligned, as when collecting from an iterator. A bare prefix
+/// gives an empty array.
+impl<W: BitStore> FromStr for BitArray<W> {
+	type Err = ParseBitArrayError;
+
+	fn from_str(s: &str) -> Result<Self, ParseBitArrayError> {
+		let error = |kind, position| ParseBitArrayError {kind, position};
+
+		let (digits, left_align) = match s.find(':') {
+			None => (s, true),
+			Some(i) => match &s[i+1..] {
+				"L" => (&s[..i], true),
+				"R" => (&s[..i], false),
+				_ => return Err(error(ParseErrorKind::InvalidSuffix, i)),
+			},
+		};
+
+		let shift = match digits.get(..2) {
+			Some("0b") => 1,
+			Some("0o") => 3,
+			Some("0x") => 4,
+			_ => return Err(error(ParseErrorKind::MissingPrefix, 0)),
+		};
+
+		let mut value = 0u128;
+		let mut len = 0u64;
+		let mut separated = false;
+		for (i, c) in digits.char_indices().skip(2) {
+			if c == '_' {
+				separated = true;
+				continue;
+			}
+			let digit = match c.to_digit(1 << shift) {
+				Some(digit) => digit,
+				None => return Err(error(ParseErrorKind::InvalidDigit(c), i)),
+			};
+			if len + shift > W::BITS {
+				return Err(error(
+					ParseErrorKind::TooLong {capacity: W::BITS},
+					i,
+				));
+			}
+			value = (value << shift) | digit as u128;
+			len += shift;
+		}
+		if len == 0 && separated {
+			return Err(error(ParseErrorKind::NoDigits, digits.len()));
+		}
+
+		Ok(W::new(W::from_u128(value), len, left_align)
+			.expect("digits fit within the capacity"))
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse() {
+		let bits: BitArray = "0b1011_0010".parse().unwrap();
+		assert_eq!(bits, BitArray::<u64>::new(0b1011_0010, 8, true).unwrap());
+
+		let bits: BitArray<u16> = "0b0001:R".parse().unwrap();
+		assert_eq!(bits.length(), 4);
+		assert!(!bits.is_left_aligned());
+		assert_eq!(u16::from(bits), 1);
+
+		let bits: BitArray<u16> = "0x0_aF:L".parse().unwrap();
+		assert_eq!(bits, BitArray::<u16>::new(0x0af, 12, true).unwrap());
+
+		let bits: BitArray<u8> = "0o17".parse().unwrap();
+		assert_eq!(bits, BitArray::<u8>::new(0o17, 6, true).unwrap());
+
+		let bits: BitArray<u8> = "0x:R".parse().unwrap();
+		assert_eq!(bits, BitArray::<u8>::zeros(0, false).unwrap());
+	}
+
+	#[test]
+	fn display_roundtrip() {
+		let bits = BitArray::<u32>::new(0b00_1011_0001, 10, true).unwrap();
+		assert_eq!(bits.to_string().parse::<BitArray<u32>>(), Ok(bits));
+	}
+
+	#[test]
+	fn parse_errors() {
+		let parse = |s: &str| s.parse::<BitArray<u8>>().unwrap_err();
+
+		assert_eq!(parse("1011").kind(), ParseErrorKind::MissingPrefix);
+		assert_eq!(parse("0b_").kind(), ParseErrorKind::NoDigits);
+		assert_eq!(parse("0b__:L").position(), 4);
+
+		let err = parse("0b10_21");
+		assert_eq!(err.kind(), ParseErrorKind::InvalidDigit('2'));
+		assert_eq!(err.position(), 5);
+
+		let err = parse("0b1:X");
+		assert_eq!(err.kind(), ParseErrorKind::InvalidSuffix);
+		assert_eq!(err.position(), 3);
+
+		let err = parse("0x1ff");
+		assert_eq!(err.kind(), ParseErrorKind::TooLong {capacity: 8});
+		assert_eq!(err.position(), 4);
+		assert_eq!(
+			err.to_string(),
+			"digits from position 4 exceed the 8-bit capacity",
+		);
+	}
+}
@@ -0,0 +1,997 @@
+use core::cmp::Ordering;
+use core::error::Error;
+use core::fmt;
+use core::iter::{Extend, FromIterator};
+use core::ops::{BitAnd, BitOr};
+use core::slice;
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+use crate::bitarray::BitArray;
+use crate::bitset::BitSet;
+use crate::iter::Indices;
+
+
+// The leading words of a serialized bitmap, from the Roaring format
+// specification; the first also carries the container count.
+const SERIAL_COOKIE: u32 = 12347;
+const SERIAL_COOKIE_NO_RUNCONTAINER: u32 = 12346;
+// With run containers, only this many containers or more get offsets.
+const NO_OFFSET_THRESHOLD: usize = 4;
+// Containers holding more values than this are stored as bitmaps.
+const ARRAY_LIMIT: u32 = 4096;
+const BITMAP_WORDS: usize = 1024;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoaringError {
+	/// The input ends partway through the bitmap.
+	UnexpectedEof,
+	/// The input does not start with either Roaring cookie.
+	InvalidCookie { cookie: u32 },
+	/// The container keys are not strictly increasing.
+	UnsortedKeys { key: u16 },
+	/// A container's contents disagree with its header.
+	InvalidContainer { key: u16 },
+}
+
+impl fmt::Display for RoaringError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			RoaringError::UnexpectedEof =>
+				write!(f, "the input ends partway through the bitmap"),
+			RoaringError::InvalidCookie { cookie } =>
+				write!(f, "unknown cookie {:#x}", cookie),
+			RoaringError::UnsortedKeys { key } =>
+				write!(f, "container key {:#x} is out of order", key),
+			RoaringError::InvalidContainer { key } =>
+				write!(f, "container {:#x} disagrees with its header", key),
+		}
+	}
+}
+
+impl Error for RoaringError {}
+
+
+// The values sharing the high 16 bits of a key, held in whichever of the
+// three Roaring layouts suits them.
+#[derive(Debug, Clone)]
+enum Container {
+	// Sorted, distinct values; at most `ARRAY_LIMIT` of them.
+	Array(Vec<u16>),
+	// Value `i` is bit `i % 64` of word `i / 64`.
+	Bitmap { words: Box<[u64; BITMAP_WORDS]>, len: u32 },
+	// Sorted, disjoint, inclusive ranges.
+	Run(Vec<(u16, u16)>),
+}
+
+impl Container {
+	// Stores sorted, distinct values as an array or a bitmap.
+	fn from_sorted(values: Vec<u16>) -> Self {
+		if values.len() as u32 <= ARRAY_LIMIT {
+			return Container::Array(values);
+		}
+		let mut words = Box::new([0; BITMAP_WORDS]);
+		for &value in &values {
+			words[value as usize / 64] |= 1 << (value % 64);
+		}
+		Container::Bitmap {words, len: values.len() as u32}
+	}
+
+	// Stores a full set of bitmap words as a bitmap or, if sparse enough,
+	// an array.
+	fn from_words(words: Box<[u64; BITMAP_WORDS]>) -> Self {
+		let len = count_words(&words[..]);
+		if len > ARRAY_LIMIT {
+			return Container::Bitmap {words, len};
+		}
+		Container::Array(Values::bitmap(&words[..]).collect())
+	}
+
+	fn to_words(&self) -> Box<[u64; BITMAP_WORDS]> {
+		match self {
+			Container::Bitmap { words, .. } => words.clone(),
+			_ => {
+				let mut words = Box::new([0; BITMAP_WORDS]);
+				for value in self.values() {
+					words[value as usize / 64] |= 1 << (value % 64);
+				}
+				words
+			}
+		}
+	}
+
+	// Applies `func` word by word to the bitmap forms of both containers.
+	fn combine_words<F>(&self, other: &Self, func: F) -> Self
+		where F: Fn(u64, u64) -> u64
+	{
+		let mut words = self.to_words();
+		for (word, &rhs) in words.iter_mut().zip(other.to_words().iter()) {
+			*word = func(*word, rhs);
+		}
+		Container::from_words(words)
+	}
+
+	fn len(&self) -> u32 {
+		match self {
+			Container::Array(values) => values.len() as u32,
+			Container::Bitmap { len, .. } => *len,
+			Container::Run(runs) => runs.iter()
+				.map(|&(start, end)| (end - start) as u32 + 1)
+				.sum(),
+		}
+	}
+
+	fn values(&self) -> Values<'_> {
+		match self {
+			Container::Array(values) => Values::Array(values.iter()),
+			Container::Bitmap { words, .. } => Values::bitmap(&words[..]),
+			Container::Run(runs) => Values::Run {
+				runs: runs.iter(),
+				next: 1,
+				end: 0,
+			},
+		}
+	}
+
+	fn contains(&self, low: u16) -> bool {
+		match self {
+			Container::Array(values) => values.binary_search(&low).is_ok(),
+			Container::Bitmap { words, .. } =>
+				word_set(words[low as usize / 64]).contains(low as u64 % 64),
+			Container::Run(runs) => runs
+				.binary_search_by(|&(start, end)| {
+					if end < low {Ordering::Less}
+					else if start > low {Ordering::Greater}
+					else {Ordering::Equal}
+				})
+				.is_ok(),
+		}
+	}
+
+	// Changes to a run container unpack it into an array or a bitmap; see
+	// `RoaringBitmap::run_optimize`.
+	fn unpack(&mut self) {
+		if let Container::Run(_) = self {
+			*self = Container::from_sorted(self.values().collect());
+		}
+	}
+
+	fn insert(&mut self, low: u16) -> bool {
+		if self.contains(low) {
+			return false;
+		}
+		self.unpack();
+		match self {
+			Container::Array(values) => {
+				let index = values.binary_search(&low).unwrap_err();
+				values.insert(index, low);
+				if values.len() as u32 > ARRAY_LIMIT {
+					*self = Container::from_sorted(core::mem::take(values));
+				}
+			}
+			Container::Bitmap { words, len } => {
+				words[low as usize / 64] |= 1 << (low % 64);
+				*len += 1;
+			}
+			Container::Run(_) => unreachable!(),
+		}
+		true
+	}
+
+	fn remove(&mut self, low: u16) -> bool {
+		if !self.contains(low) {
+			return false;
+		}
+		self.unpack();
+		match self {
+			Container::Array(values) => {
+				values.retain(|&value| value != low);
+			}
+			Container::Bitmap { words, len } => {
+				words[low as usize / 64] &= !(1 << (low % 64));
+				*len -= 1;
+				if *len <= ARRAY_LIMIT {
+					*self = Container::Array(self.values().collect());
+				}
+			}
+			Container::Run(_) => unreachable!(),
+		}
+		true
+	}
+
+	// Counts the values no greater than `low`.
+	fn rank(&self, low: u16) -> u32 {
+		match self {
+			Container::Array(values) =>
+				values.partition_point(|&value| value <= low) as u32,
+			Container::Bitmap { words, .. } => {
+				let (word, bit) = (low as usize / 64, low as u64 % 64);
+				let within = word_set(words[word]).as_bit_array().trim_to(bit + 1);
+				count_words(&words[..word]) + within.count_ones() as u32
+			}
+			Container::Run(runs) => runs.iter()
+				.take_while(|&&(start, _)| start <= low)
+				.map(|&(start, end)| (end.min(low) - start) as u32 + 1)
+				.sum(),
+		}
+	}
+
+	// Returns the value with `n` smaller values; `n` must be below `len()`.
+	fn select(&self, mut n: u32) -> u16 {
+		match self {
+			Container::Array(values) => values[n as usize],
+			Container::Bitmap { words, .. } => {
+				for (word, &bits) in words.iter().enumerate() {
+					let set = word_set(bits);
+					let len = set.len() as u32;
+					if n < len {
+						let bit = set.iter().nth(n as usize).unwrap();
+						return (word as u64 * 64 + bit) as u16;
+					}
+					n -= len;
+				}
+				unreachable!()
+			}
+			Container::Run(runs) => {
+				for &(start, end) in runs {
+					let len = (end - start) as u32 + 1;
+					if n < len {
+						return start + n as u16;
+					}
+					n -= len;
+				}
+				unreachable!()
+			}
+		}
+	}
+
+	fn union(&self, other: &Self) -> Self {
+		if let (Container::Array(lhs), Container::Array(rhs)) = (self, other) {
+			let mut values = Vec::with_capacity(lhs.len() + rhs.len());
+			let (mut i, mut j) = (0, 0);
+			while i < lhs.len() && j < rhs.len() {
+				match lhs[i].cmp(&rhs[j]) {
+					Ordering::Less => {values.push(lhs[i]); i += 1;}
+					Ordering::Greater => {values.push(rhs[j]); j += 1;}
+					Ordering::Equal => {values.push(lhs[i]); i += 1; j += 1;}
+				}
+			}
+			values.extend_from_slice(&lhs[i..]);
+			values.extend_from_slice(&rhs[j..]);
+			return Container::from_sorted(values);
+		}
+		self.combine_words(other, |lhs, rhs| lhs | rhs)
+	}
+
+	fn intersection(&self, other: &Self) -> Self {
+		match (self, other) {
+			(Container::Array(values), other)
+			| (other, Container::Array(values)) => Container::Array(
+				values.iter().copied()
+					.filter(|&value| other.contains(value))
+					.collect()
+			),
+			_ => self.combine_words(other, |lhs, rhs| lhs & rhs),
+		}
+	}
+
+	// Switches to whichever layout serializes smallest.
+	fn optimize(&mut self) {
+		let mut runs: Vec<(u16, u16)> = Vec::new();
+		for value in self.values() {
+			match runs.last_mut() {
+				Some((_, end)) if *end as u32 + 1 == value as u32 =>
+					*end = value,
+				_ => runs.push((value, value)),
+			}
+		}
+		let unpacked = 2 * core::cmp::min(self.len(), ARRAY_LIMIT) as usize;
+		if 2 + 4 * runs.len() < unpacked {
+			*self = Container::Run(runs);
+		} else {
+			self.unpack();
+		}
+	}
+
+	fn serialized_size(&self) -> usize {
+		match self {
+			Container::Array(values) => 2 * values.len(),
+			Container::Bitmap { .. } => 8 * BITMAP_WORDS,
+			Container::Run(runs) => 2 + 4 * runs.len(),
+		}
+	}
+
+	fn write(&self, bytes: &mut Vec<u8>) {
+		match self {
+			Container::Array(values) => for value in values {
+				bytes.extend_from_slice(&value.to_le_bytes());
+			},
+			Container::Bitmap { words, .. } => for word in words.iter() {
+				bytes.extend_from_slice(&word.to_le_bytes());
+			},
+			Container::Run(runs) => {
+				bytes.extend_from_slice(&(runs.len() as u16).to_le_bytes());
+				for &(start, end) in runs {
+					bytes.extend_from_slice(&start.to_le_bytes());
+					bytes.extend_from_slice(&(end - start).to_le_bytes());
+				}
+			}
+		}
+	}
+
+	// Reads a container of `len` values, checking it against its header.
+	fn read(input: &mut Input, key: u16, len: u32, is_run: bool)
+		-> Result<Self, RoaringError>
+	{
+		let invalid = RoaringError::InvalidContainer {key};
+		let container = if is_run {
+			let count = input.u16()? as usize;
+			let mut runs = Vec::with_capacity(count);
+			let mut next = 0u32;
+			for _ in 0..count {
+				let (start, extra) = (input.u16()?, input.u16()?);
+				let end = start.checked_add(extra).ok_or(invalid)?;
+				if (start as u32) < next {
+					return Err(invalid);
+				}
+				next = end as u32 + 1;
+				runs.push((start, end));
+			}
+			Container::Run(runs)
+		} else if len <= ARRAY_LIMIT {
+			let mut values = Vec::with_capacity(len as usize);
+			for _ in 0..len {
+				let value = input.u16()?;
+				if values.last().is_some_and(|&last| last >= value) {
+					return Err(invalid);
+				}
+				values.push(value);
+			}
+			Container::Array(values)
+		} else {
+			let mut words = Box::new([0; BITMAP_WORDS]);
+			for word in words.iter_mut() {
+				*word = u64::from_le_bytes(input.array()?);
+			}
+			Container::Bitmap {words, len}
+		};
+
+		let count = match &container {
+			Container::Bitmap { words, .. } => count_words(&words[..]),
+			container => container.len(),
+		};
+		if count != len {
+			return Err(invalid);
+		}
+		Ok(container)
+	}
+}
+
+// Views a bitmap word as the set of its bit positions.
+fn word_set(word: u64) -> BitSet {
+	BitSet::from(BitArray::<u64>::new(word, 64, false).unwrap())
+}
+
+fn count_words(words: &[u64]) -> u32 {
+	words.iter().map(|word| word.count_ones()).sum()
+}
+
+// Iterates over the values of a single container.
+#[derive(Debug, Clone)]
+enum Values<'a> {
+	Array(slice::Iter<'a, u16>),
+	Bitmap { words: slice::Iter<'a, u64>, base: u64, ones: Indices<u64> },
+	Run { runs: slice::Iter<'a, (u16, u16)>, next: u32, end: u32 },
+}
+
+impl<'a> Values<'a> {
+	fn bitmap(words: &'a [u64]) -> Self {
+		Values::Bitmap {
+			words: words.iter(),
+			base: 0,
+			ones: BitSet::<u64>::new().iter(),
+		}
+	}
+}
+
+impl Iterator for Values<'_> {
+	type Item = u16;
+
+	fn next(&mut self) -> Option<u16> {
+		match self {
+			Values::Array(values) => values.next().copied(),
+			Values::Bitmap { words, base, ones } => loop {
+				if let Some(bit) = ones.next() {
+					return Some((*base + bit) as u16);
+				}
+				*ones = word_set(*words.next()?).iter();
+				*base = 64 * (BITMAP_WORDS - 1 - words.len()) as u64;
+			},
+			Values::Run { runs, next, end } => loop {
+				if next <= end {
+					*next += 1;
+					return Some((*next - 1) as u16);
+				}
+				let &(start, last) = runs.next()?;
+				*next = start as u32;
+				*end = last as u32;
+			},
+		}
+	}
+}
+
+
+// Reads little-endian fields off the front of a byte slice.
+struct Input<'a> {
+	bytes: &'a [u8],
+}
+
+impl<'a> Input<'a> {
+	fn take(&mut self, n: usize) -> Result<&'a [u8], RoaringError> {
+		if n > self.bytes.len() {
+			return Err(RoaringError::UnexpectedEof);
+		}
+		let (head, tail) = self.bytes.split_at(n);
+		self.bytes = tail;
+		Ok(head)
+	}
+
+	fn array<const N: usize>(&mut self) -> Result<[u8; N], RoaringError> {
+		let mut array = [0; N];
+		array.copy_from_slice(self.take(N)?);
+		Ok(array)
+	}
+
+	fn u16(&mut self) -> Result<u16, RoaringError> {
+		self.array().map(u16::from_le_bytes)
+	}
+
+	fn u32(&mut self) -> Result<u32, RoaringError> {
+		self.array().map(u32::from_le_bytes)
+	}
+}
+
+
+/// A compressed set of `u32`s, laid out as in the Roaring bitmap format.
+///
+/// The values are grouped by their high 16 bits into containers, each of
+/// which holds its low 16 bits as a sorted array, as a bitmap of 1024 64-bit
+/// words, or as a list of runs. Arrays and bitmaps are swapped automatically as
+/// containers fill and empty; runs are only chosen by `run_optimize`.
+#[derive(Debug, Clone, Default)]
+pub struct RoaringBitmap {
+	// Sorted by key, with no empty containers.
+	containers: Vec<(u16, Container)>,
+}
+
+impl PartialEq for RoaringBitmap {
+	fn eq(&self, other: &Self) -> bool {
+		self.len() == other.len() && self.iter().eq(other.iter())
+	}
+}
+
+impl Eq for RoaringBitmap {}
+
+fn split(value: u32) -> (u16, u16) {
+	((value >> 16) as u16, value as u16)
+}
+
+impl RoaringBitmap {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The number of values in the bitmap.
+	pub fn len(&self) -> u64 {
+		self.containers.iter().map(|(_, c)| c.len() as u64).sum()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.containers.is_empty()
+	}
+
+	fn find(&self, key: u16) -> Result<usize, usize> {
+		self.containers.binary_search_by_key(&key, |&(key, _)| key)
+	}
+
+	pub fn contains(&self, value: u32) -> bool {
+		let (key, low) = split(value);
+		self.find(key)
+			.is_ok_and(|index| self.containers[index].1.contains(low))
+	}
+
+	/// Adds `value`, returning whether it was absent.
+	pub fn insert(&mut self, value: u32) -> bool {
+		let (key, low) = split(value);
+		match self.find(key) {
+			Ok(index) => self.containers[index].1.insert(low),
+			Err(index) => {
+				let container = Container::Array(vec![low]);
+				self.containers.insert(index, (key, container));
+				true
+			}
+		}
+	}
+
+	/// Removes `value`, returning whether it was present.
+	pub fn remove(&mut self, value: u32) -> bool {
+		let (key, low) = split(value);
+		let index = match self.find(key) {
+			Ok(index) => index,
+			Err(_) => return false,
+		};
+		let removed = self.containers[index].1.remove(low);
+		if self.containers[index].1.len() == 0 {
+			self.containers.remove(index);
+		}
+		removed
+	}
+
+	/// Iterates over the values in ascending order.
+	pub fn iter(&self) -> Iter<'_> {
+		Iter {
+			containers: self.containers.iter(),
+			key: 0,
+			values: Values::Array([].iter()),
+		}
+	}
+
+	/// Counts the values no greater than `value`.
+	pub fn rank(&self, value: u32) -> u64 {
+		let (key, low) = split(value);
+		let mut rank = 0;
+		for (k, container) in &self.containers {
+			match k.cmp(&key) {
+				Ordering::Less => rank += container.len() as u64,
+				Ordering::Equal => return rank + container.rank(low) as u64,
+				Ordering::Greater => break,
+			}
+		}
+		rank
+	}
+
+	/// Returns the value with `n` smaller values in the bitmap, or `None`
+	/// if the bitmap holds `n` values or fewer.
+	pub fn select(&self, mut n: u64) -> Option<u32> {
+		for (key, container) in &self.containers {
+			let len = container.len() as u64;
+			if n < len {
+				let low = container.select(n as u32);
+				return Some((*key as u32) << 16 | low as u32);
+			}
+			n -= len;
+		}
+		None
+	}
+
+	pub fn union(&self, other: &Self) -> Self {
+		let (lhs, rhs) = (&self.containers, &other.containers);
+		let mut containers = Vec::with_capacity(lhs.len() + rhs.len());
+		let (mut i, mut j) = (0, 0);
+		while i < lhs.len() && j < rhs.len() {
+			match lhs[i].0.cmp(&rhs[j].0) {
+				Ordering::Less => {containers.push(lhs[i].clone()); i += 1;}
+				Ordering::Greater => {containers.push(rhs[j].clone()); j += 1;}
+				Ordering::Equal => {
+					containers.push((lhs[i].0, lhs[i].1.union(&rhs[j].1)));
+					i += 1;
+					j += 1;
+				}
+			}
+		}
+		containers.extend_from_slice(&lhs[i..]);
+		containers.extend_from_slice(&rhs[j..]);
+		Self {containers}
+	}
+
+	pub fn intersection(&self, other: &Self) -> Self {
+		let (lhs, rhs) = (&self.containers, &other.containers);
+		let mut containers = Vec::new();
+		let (mut i, mut j) = (0, 0);
+		while i < lhs.len() && j < rhs.len() {
+			match lhs[i].0.cmp(&rhs[j].0) {
+				Ordering::Less => i += 1,
+				Ordering::Greater => j += 1,
+				Ordering::Equal => {
+					let container = lhs[i].1.intersection(&rhs[j].1);
+					if container.len() > 0 {
+						containers.push((lhs[i].0, container));
+					}
+					i += 1;
+					j += 1;
+				}
+			}
+		}
+		Self {containers}
+	}
+
+	/// Stores each container in whichever layout serializes smallest,
+	/// switching runs of consecutive values to run containers.
+	pub fn run_optimize(&mut self) {
+		for (_, container) in &mut self.containers {
+			container.optimize();
+		}
+	}
+
+	fn has_runs(&self) -> bool {
+		self.containers.iter()
+			.any(|(_, container)| matches!(container, Container::Run(_)))
+	}
+
+	fn has_offsets(&self) -> bool {
+		!self.has_runs() || self.containers.len() >= NO_OFFSET_THRESHOLD
+	}
+
+	/// The number of bytes that `to_bytes` returns.
+	pub fn serialized_size(&self) -> usize {
+		let count = self.containers.len();
+		let header =
+			if self.has_runs() {4 + count.div_ceil(8)}
+			else {8};
+		let offsets = if self.has_offsets() {4 * count} else {0};
+		header + 4 * count + offsets + self.containers.iter()
+			.map(|(_, container)| container.serialized_size())
+			.sum::<usize>()
+	}
+
+	/// Serializes the bitmap in the portable Roaring format shared by the
+	/// Java, Go and C implementations.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let count = self.containers.len();
+		let mut bytes = Vec::with_capacity(self.serialized_size());
+		if self.has_runs() {
+			let cookie = SERIAL_COOKIE | (count as u32 - 1) << 16;
+			bytes.extend_from_slice(&cookie.to_le_bytes());
+			let mut flags = vec![0u8; count.div_ceil(8)];
+			for (index, (_, container)) in self.containers.iter().enumerate() {
+				if let Container::Run(_) = container {
+					flags[index / 8] |= 1 << (index % 8);
+				}
+			}
+			bytes.extend_from_slice(&flags);
+		} else {
+			let cookie = SERIAL_COOKIE_NO_RUNCONTAINER;
+			bytes.extend_from_slice(&cookie.to_le_bytes());
+			bytes.extend_from_slice(&(count as u32).to_le_bytes());
+		}
+
+		for (key, container) in &self.containers {
+			bytes.extend_from_slice(&key.to_le_bytes());
+			bytes.extend_from_slice(&((container.len() - 1) as u16).to_le_bytes());
+		}
+		if self.has_offsets() {
+			let mut offset = bytes.len() + 4 * count;
+			for (_, container) in &self.containers {
+				bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+				offset += container.serialized_size();
+			}
+		}
+		for (_, container) in &self.containers {
+			container.write(&mut bytes);
+		}
+		bytes
+	}
+
+	/// Deserializes a bitmap in the portable Roaring format, ignoring any
+	/// bytes that follow it.
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, RoaringError> {
+		let mut input = Input {bytes};
+		let cookie = input.u32()?;
+		let (count, flags) =
+			if cookie == SERIAL_COOKIE_NO_RUNCONTAINER {
+				(input.u32()? as usize, None)
+			} else if cookie & 0xffff == SERIAL_COOKIE {
+				let count = (cookie >> 16) as usize + 1;
+				(count, Some(input.take(count.div_ceil(8))?))
+			} else {
+				return Err(RoaringError::InvalidCookie {cookie});
+			};
+
+		let header_len = count.checked_mul(4)
+			.ok_or(RoaringError::UnexpectedEof)?;
+		let mut header = Input {bytes: input.take(header_len)?};
+		if flags.is_none() || count >= NO_OFFSET_THRESHOLD {
+			input.take(header_len)?;
+		}
+
+		let mut containers: Vec<(u16, Container)> = Vec::with_capacity(count);
+		for index in 0..count {
+			let (key, len) = (header.u16()?, header.u16()? as u32 + 1);
+			if containers.last().is_some_and(|&(last, _)| last >= key) {
+				return Err(RoaringError::UnsortedKeys {key});
+			}
+			let is_run = flags
+				.is_some_and(|flags| flags[index / 8] >> (index % 8) & 1 == 1);
+			let container = Container::read(&mut input, key, len, is_run)?;
+			containers.push((key, container));
+		}
+		Ok(Self {containers})
+	}
+
+	/// Writes the bytes that `to_bytes` returns.
+	#[cfg(feature = "std")]
+	pub fn serialize_into<T: Write>(&self, mut writer: T) -> io::Result<()> {
+		writer.write_all(&self.to_bytes())
+	}
+}
+
+impl BitOr for &RoaringBitmap {
+	type Output = RoaringBitmap;
+
+	fn bitor(self, other: &RoaringBitmap) -> RoaringBitmap {
+		self.union(other)
+	}
+}
+
+impl BitAnd for &RoaringBitmap {
+	type Output = RoaringBitmap;
+
+	fn bitand(self, other: &RoaringBitmap) -> RoaringBitmap {
+		self.intersection(other)
+	}
+}
+
+impl Extend<u32> for RoaringBitmap {
+	fn extend<I: IntoIterator<Item = u32>>(&mut self, iter: I) {
+		for value in iter {
+			self.insert(value);
+		}
+	}
+}
+
+impl FromIterator<u32> for RoaringBitmap {
+	fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+		let mut bitmap = Self::new();
+		bitmap.extend(iter);
+		bitmap
+	}
+}
+
+impl<'a> IntoIterator for &'a RoaringBitmap {
+	type Item = u32;
+	type IntoIter = Iter<'a>;
+
+	fn into_iter(self) -> Iter<'a> {
+		self.iter()
+	}
+}
+
+
+/// Iterates over the values of a `RoaringBitmap` in ascending order.
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+	containers: slice::Iter<'a, (u16, Container)>,
+	key: u32,
+	values: Values<'a>,
+}
+
+impl Iterator for Iter<'_> {
+	type Item = u32;
+
+	fn next(&mut self) -> Option<u32> {
+		loop {
+			if let Some(low) = self.values.next() {
+				return Some(self.key << 16 | low as u32);
+			}
+			let (key, container) = self.containers.next()?;
+			self.key = *key as u32;
+			self.values = container.values();
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::BTreeSet;
+
+	// Mixes sparse values, a dense block and a long run across a few
+	// containers, so that every layout turns up.
+	fn sample(seed: u64) -> BTreeSet<u32> {
+		let mut state = seed;
+		let mut next = move || {
+			state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+			(state >> 33) as u32
+		};
+		let mut values = BTreeSet::new();
+		for _ in 0..300 {
+			values.insert(next() % (4 << 16));
+		}
+		for _ in 0..6000 {
+			values.insert((1 << 16) + next() % 0x8000);
+		}
+		let start = (2 << 16) + next() % 0x1000;
+		values.extend(start..start + 9000);
+		values
+	}
+
+	fn kinds(bitmap: &RoaringBitmap) -> Vec<&'static str> {
+		bitmap.containers.iter().map(|(_, container)| match container {
+			Container::Array(_) => "array",
+			Container::Bitmap { .. } => "bitmap",
+			Container::Run(_) => "run",
+		}).collect()
+	}
+
+	#[test]
+	fn insert_and_remove() {
+		let mut bitmap = RoaringBitmap::new();
+		assert!(bitmap.is_empty());
+		assert!(bitmap.insert(7));
+		assert!(!bitmap.insert(7));
+		assert!(bitmap.insert(u32::MAX));
+		assert!(bitmap.contains(u32::MAX) && !bitmap.contains(8));
+		assert_eq!(bitmap.iter().collect::<Vec<_>>(), [7, u32::MAX]);
+
+		bitmap.extend(0..=ARRAY_LIMIT);
+		assert_eq!(kinds(&bitmap), ["bitmap", "array"]);
+		assert_eq!(bitmap.len(), 4098);
+		assert!(bitmap.remove(100));
+		assert!(!bitmap.remove(100));
+		assert_eq!(kinds(&bitmap), ["array", "array"]);
+
+		assert!(bitmap.remove(u32::MAX));
+		assert_eq!(kinds(&bitmap), ["array"]);
+		assert!(!bitmap.remove(1 << 20));
+		assert_eq!(bitmap.len(), 4096);
+	}
+
+	#[test]
+	fn matches_btreeset() {
+		for &seed in &[1, 2, 3] {
+			let values = sample(seed);
+			let mut bitmap: RoaringBitmap = values.iter().copied().collect();
+			for optimize in &[false, true] {
+				if *optimize {
+					bitmap.run_optimize();
+					assert!(kinds(&bitmap).contains(&"run"));
+				}
+				assert_eq!(bitmap.len(), values.len() as u64);
+				assert!(bitmap.iter().eq(values.iter().copied()));
+				for &value in values.iter().step_by(97) {
+					assert!(bitmap.contains(value));
+					let other = value ^ 1;
+					assert_eq!(bitmap.contains(other), values.contains(&other));
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn rank_and_select() {
+		let values = sample(4);
+		let mut bitmap: RoaringBitmap = values.iter().copied().collect();
+		for _ in 0..2 {
+			for (n, &value) in values.iter().enumerate().step_by(41) {
+				assert_eq!(bitmap.select(n as u64), Some(value));
+				assert_eq!(bitmap.rank(value), n as u64 + 1);
+				assert_eq!(bitmap.rank(value - 1), n as u64);
+			}
+			assert_eq!(bitmap.select(values.len() as u64), None);
+			assert_eq!(bitmap.rank(u32::MAX), values.len() as u64);
+			bitmap.run_optimize();
+		}
+	}
+
+	#[test]
+	fn set_algebra() {
+		let (a, b) = (sample(5), sample(6));
+		let mut lhs: RoaringBitmap = a.iter().copied().collect();
+		let rhs: RoaringBitmap = b.iter().copied().collect();
+		for _ in 0..2 {
+			assert!((&lhs | &rhs).iter().eq(a.union(&b).copied()));
+			assert!((&lhs & &rhs).iter().eq(a.intersection(&b).copied()));
+			assert!((&rhs & &lhs).iter().eq(a.intersection(&b).copied()));
+			assert_eq!(&lhs & &RoaringBitmap::new(), RoaringBitmap::new());
+			lhs.run_optimize();
+		}
+
+		let mut sparse: RoaringBitmap = (0..5000).map(|i| i * 2).collect();
+		let odd: RoaringBitmap = (0..5000).map(|i| i * 2 + 1).collect();
+		assert_eq!(kinds(&sparse), ["bitmap"]);
+		assert!(sparse.intersection(&odd).is_empty());
+		sparse = sparse.union(&odd);
+		sparse.run_optimize();
+		assert_eq!(kinds(&sparse), ["run"]);
+		assert_eq!(sparse, (0..10000).collect());
+	}
+
+	#[test]
+	fn portable_format() {
+		// {1, 2, 65539}, without runs: cookie and count, then a key and
+		// cardinality - 1 per container, then offsets, then the values.
+		let bitmap: RoaringBitmap = [1, 2, 1 << 16 | 3].iter().copied().collect();
+		let bytes = [
+			0x3a, 0x30, 0, 0, 2, 0, 0, 0,
+			0, 0, 1, 0, 1, 0, 0, 0,
+			24, 0, 0, 0, 28, 0, 0, 0,
+			1, 0, 2, 0, 3, 0,
+		];
+		assert_eq!(bitmap.to_bytes(), bytes);
+		assert_eq!(bitmap.serialized_size(), bytes.len());
+		assert_eq!(RoaringBitmap::from_bytes(&bytes), Ok(bitmap));
+
+		// 0..100 as a run: the cookie carries the container count and is
+		// followed by the run flags; too few containers for offsets.
+		let mut bitmap: RoaringBitmap = (0..100).collect();
+		bitmap.run_optimize();
+		let bytes = [
+			0x3b, 0x30, 0, 0, 1,
+			0, 0, 99, 0,
+			1, 0, 0, 0, 99, 0,
+		];
+		assert_eq!(bitmap.to_bytes(), bytes);
+		assert_eq!(RoaringBitmap::from_bytes(&bytes), Ok(bitmap));
+
+		assert_eq!(RoaringBitmap::new().to_bytes(), [0x3a, 0x30, 0, 0, 0, 0, 0, 0]);
+	}
+
+	#[test]
+	fn roundtrip() {
+		for &seed in &[7, 8] {
+			let mut bitmap: RoaringBitmap = sample(seed).into_iter().collect();
+			for _ in 0..2 {
+				let bytes = bitmap.to_bytes();
+				assert_eq!(bytes.len(), bitmap.serialized_size());
+				let read = RoaringBitmap::from_bytes(&bytes).unwrap();
+				assert_eq!(kinds(&read), kinds(&bitmap));
+				assert_eq!(read, bitmap);
+				bitmap.run_optimize();
+			}
+		}
+
+		// Enough containers that the run format carries offsets too.
+		let mut bitmap: RoaringBitmap = (0..6).map(|key| key << 16).collect();
+		bitmap.extend((5 << 16)..(5 << 16 | 10));
+		bitmap.run_optimize();
+		let bytes = bitmap.to_bytes();
+		assert_eq!(&bytes[..4], [0x3b, 0x30, 5, 0]);
+		assert_eq!(RoaringBitmap::from_bytes(&bytes), Ok(bitmap));
+	}
+
+	#[test]
+	fn invalid_input() {
+		let bitmap: RoaringBitmap = [1, 2, 1 << 16 | 3].iter().copied().collect();
+		let bytes = bitmap.to_bytes();
+		for len in 0..bytes.len() {
+			assert_eq!(
+				RoaringBitmap::from_bytes(&bytes[..len]),
+				Err(RoaringError::UnexpectedEof),
+			);
+		}
+
+		let mut bad = bytes.clone();
+		bad[0] = 0;
+		assert_eq!(
+			RoaringBitmap::from_bytes(&bad),
+			Err(RoaringError::InvalidCookie {cookie: 0x3000}),
+		);
+		let mut bad = bytes.clone();
+		bad[12] = 0;
+		assert_eq!(
+			RoaringBitmap::from_bytes(&bad),
+			Err(RoaringError::UnsortedKeys {key: 0}),
+		);
+		let mut bad = bytes.clone();
+		bad[24..26].copy_from_slice(&[2, 0]);
+		assert_eq!(
+			RoaringBitmap::from_bytes(&bad),
+			Err(RoaringError::InvalidContainer {key: 0}),
+		);
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn serialize_into() {
+		let bitmap: RoaringBitmap = sample(9).into_iter().collect();
+		let mut bytes = Vec::new();
+		bitmap.serialize_into(&mut bytes).unwrap();
+		assert_eq!(bytes, bitmap.to_bytes());
+	}
+}
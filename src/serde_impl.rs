@@ -0,0 +1,105 @@
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::bitarray::BitArray;
+use crate::bitstore::BitStore;
+
+
+// Writes the literal accepted by `FromStr`, alignment suffix included.
+struct Literal<'a, W: BitStore>(&'a BitArray<W>);
+
+impl<W: BitStore> fmt::Display for Literal<'_, W> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}:{}", self.0, if self.0.is_left_aligned() {"L"} else {"R"})
+	}
+}
+
+/// Human-readable formats get a string literal such as `"0b1011:L"`; other
+/// formats get a compact `(length, value, left_align)` tuple.
+impl<W: BitStore + Serialize> Serialize for BitArray<W> {
+	fn serialize<S: Serializer>(&self, serializer: S)
+		-> Result<S::Ok, S::Error>
+	{
+		if serializer.is_human_readable() {
+			serializer.collect_str(&Literal(self))
+		} else {
+			(self.length(), W::value(self), self.is_left_aligned())
+				.serialize(serializer)
+		}
+	}
+}
+
+struct LiteralVisitor<W>(PhantomData<W>);
+
+impl<W: BitStore> Visitor<'_> for LiteralVisitor<W> {
+	type Value = BitArray<W>;
+
+	fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("a bit array literal such as \"0b1011:L\"")
+	}
+
+	fn visit_str<E: de::Error>(self, s: &str) -> Result<BitArray<W>, E> {
+		s.parse().map_err(E::custom)
+	}
+}
+
+impl<'de, W: BitStore + Deserialize<'de>> Deserialize<'de> for BitArray<W> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D)
+		-> Result<Self, D::Error>
+	{
+		if deserializer.is_human_readable() {
+			deserializer.deserialize_str(LiteralVisitor(PhantomData))
+		} else {
+			let (len, value, left_align) =
+				<(u64, W, bool)>::deserialize(deserializer)?;
+			W::new(value, len, left_align).map_err(de::Error::custom)
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn json_roundtrip() {
+		let bits = BitArray::<u16>::new(0b0010_1101, 8, false).unwrap();
+		let json = serde_json::to_string(&bits).unwrap();
+		assert_eq!(json, "\"0b00101101:R\"");
+		assert_eq!(serde_json::from_str::<BitArray<u16>>(&json).unwrap(), bits);
+
+		let bits = BitArray::<u128>::ones(100, true).unwrap();
+		let json = serde_json::to_string(&bits).unwrap();
+		assert_eq!(serde_json::from_str::<BitArray<u128>>(&json).unwrap(), bits);
+
+		let bits = BitArray::<u8>::zeros(0, false).unwrap();
+		let json = serde_json::to_string(&bits).unwrap();
+		assert_eq!(json, "\"0b:R\"");
+		assert_eq!(serde_json::from_str::<BitArray<u8>>(&json).unwrap(), bits);
+	}
+
+	#[test]
+	fn json_errors() {
+		assert!(serde_json::from_str::<BitArray<u8>>("\"0b101:X\"").is_err());
+		assert!(serde_json::from_str::<BitArray<u8>>("\"0x123\"").is_err());
+		assert!(serde_json::from_str::<BitArray<u8>>("5").is_err());
+	}
+
+	#[test]
+	fn bincode_roundtrip() {
+		let bits = BitArray::<u16>::new(0b0010_1101, 8, true).unwrap();
+		let bytes = bincode::serialize(&bits).unwrap();
+		assert_eq!(bytes.len(), 8 + 2 + 1);
+		assert_eq!(bincode::deserialize::<BitArray<u16>>(&bytes).unwrap(), bits);
+	}
+
+	#[test]
+	fn bincode_rejects_stray_bits() {
+		let bytes = bincode::serialize(&(4u64, 0xffu8, false)).unwrap();
+		assert!(bincode::deserialize::<BitArray<u8>>(&bytes).is_err());
+	}
+}
@@ -0,0 +1,514 @@
+use core::cmp;
+use core::iter::{DoubleEndedIterator, ExactSizeIterator};
+use core::ops::{BitAndAssign, BitOrAssign, BitXorAssign, RangeBounds};
+
+use crate::bitarray::check_range;
+use crate::bitstore::BitStore;
+
+
+// Selects the lowest `n` bits of a word.
+fn low_mask<W: BitStore>(n: u64) -> W {
+	if n >= W::BITS {W::ONES} else {!(W::ONES << n)}
+}
+
+// Reads `n <= W::BITS` bits starting `position` bits into `words`. In
+// index order the first bit is the most significant of the result when
+// left-aligned and the least significant otherwise.
+fn read<W: BitStore>(words: &[W], left_align: bool, position: u64, n: u64)
+	-> W
+{
+	if n == 0 {
+		return W::ZERO;
+	}
+	let (word, offset) = ((position / W::BITS) as usize, position % W::BITS);
+	let spill = offset + n > W::BITS;
+	if left_align {
+		let mut top = words[word] << offset;
+		if spill {
+			top = top | (words[word + 1] >> (W::BITS - offset));
+		}
+		top >> (W::BITS - n)
+	} else {
+		let mut low = words[word] >> offset;
+		if spill {
+			low = low | (words[word + 1] << (W::BITS - offset));
+		}
+		low & low_mask(n)
+	}
+}
+
+// Overwrites the `n <= W::BITS` bits that `read` would return.
+fn write<W: BitStore>(
+	words: &mut [W],
+	left_align: bool,
+	position: u64,
+	n: u64,
+	value: W,
+) {
+	if n == 0 {
+		return;
+	}
+	let (word, offset) = ((position / W::BITS) as usize, position % W::BITS);
+	let spill = offset + n > W::BITS;
+	let (first, second) = if left_align {
+		let top = value << (W::BITS - n);
+		let mask = low_mask::<W>(n) << (W::BITS - n);
+		((top >> offset, mask >> offset), spill.then(|| (
+			top << (W::BITS - offset),
+			mask << (W::BITS - offset),
+		)))
+	} else {
+		let value = value & low_mask(n);
+		let mask = low_mask::<W>(n);
+		((value << offset, mask << offset), spill.then(|| (
+			value >> (W::BITS - offset),
+			mask >> (W::BITS - offset),
+		)))
+	};
+
+	words[word] = (words[word] & !first.1) | first.0;
+	if let Some((bits, mask)) = second {
+		words[word + 1] = (words[word + 1] & !mask) | bits;
+	}
+}
+
+
+/// A borrowed view of a run of bits within a buffer of words.
+///
+/// Bits are numbered through the buffer word by word, from the most
+/// significant bit of each word when left-aligned and from the least
+/// significant otherwise; a `u8` buffer read left-aligned follows network
+/// bit order. The view starts and ends at any bit, not just at word
+/// boundaries.
+#[derive(Debug, Clone, Copy)]
+pub struct BitSlice<'a, W: BitStore = u64> {
+	words: &'a [W],
+	start: u64,
+	len: u64,
+	left_align: bool,
+}
+
+impl<'a, W: BitStore> BitSlice<'a, W> {
+	/// Views every bit of `words`.
+	pub fn new(words: &'a [W], left_align: bool) -> Self {
+		Self {
+			words,
+			start: 0,
+			len: W::BITS * words.len() as u64,
+			left_align,
+		}
+	}
+
+	pub fn length(&self) -> u64 {
+		self.len
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	pub fn is_left_aligned(&self) -> bool {
+		self.left_align
+	}
+
+	/// The number of bits of the buffer before the view.
+	pub fn start_offset(&self) -> u64 {
+		self.start
+	}
+
+	/// The number of bits of the buffer after the view.
+	pub fn end_offset(&self) -> u64 {
+		W::BITS * self.words.len() as u64 - self.start - self.len
+	}
+
+	/// Returns the bit at `index`, or `None` if `index` is out of range.
+	pub fn get(&self, index: u64) -> Option<bool> {
+		if index >= self.len {
+			return None;
+		}
+		Some(self.read(index, 1) != W::ZERO)
+	}
+
+	/// Narrows the view to the bits in `range`.
+	///
+	/// Panics if the range does not lie within the view.
+	pub fn slice<R: RangeBounds<u64>>(&self, range: R) -> BitSlice<'a, W> {
+		let (start, end) = check_range(range, self.len);
+		Self {
+			start: self.start + start,
+			len: end - start,
+			..*self
+		}
+	}
+
+	pub fn iter(&self) -> Iter<'a, W> {
+		Iter {
+			bits: *self,
+			front: 0,
+			back: self.len,
+		}
+	}
+
+	pub fn count_ones(&self) -> u64 {
+		self.chunks().map(|(index, n)| self.read(index, n).count_ones()).sum()
+	}
+
+	// Yields the position and width of each word-sized run of the view.
+	fn chunks(&self) -> impl Iterator<Item = (u64, u64)> {
+		let len = self.len;
+		(0..len).step_by(W::BITS as usize)
+			.map(move |i| (i, cmp::min(W::BITS, len - i)))
+	}
+
+	fn read(&self, index: u64, n: u64) -> W {
+		read(self.words, self.left_align, self.start + index, n)
+	}
+}
+
+impl<'a, W: BitStore> IntoIterator for BitSlice<'a, W> {
+	type Item = bool;
+	type IntoIter = Iter<'a, W>;
+
+	fn into_iter(self) -> Iter<'a, W> {
+		self.iter()
+	}
+}
+
+
+/// A mutable borrowed view of a run of bits within a buffer of words,
+/// numbered as in `BitSlice`.
+#[derive(Debug)]
+pub struct BitSliceMut<'a, W: BitStore = u64> {
+	words: &'a mut [W],
+	start: u64,
+	len: u64,
+	left_align: bool,
+}
+
+impl<'a, W: BitStore> BitSliceMut<'a, W> {
+	/// Views every bit of `words`.
+	pub fn new(words: &'a mut [W], left_align: bool) -> Self {
+		let len = W::BITS * words.len() as u64;
+		Self {words, start: 0, len, left_align}
+	}
+
+	/// Reborrows the view as read-only.
+	pub fn as_slice(&self) -> BitSlice<'_, W> {
+		BitSlice {
+			words: self.words,
+			start: self.start,
+			len: self.len,
+			left_align: self.left_align,
+		}
+	}
+
+	pub fn length(&self) -> u64 {
+		self.len
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	pub fn is_left_aligned(&self) -> bool {
+		self.left_align
+	}
+
+	/// Returns the bit at `index`, or `None` if `index` is out of range.
+	pub fn get(&self, index: u64) -> Option<bool> {
+		self.as_slice().get(index)
+	}
+
+	/// Sets the bit at `index`.
+	///
+	/// Panics if `index` is out of range.
+	pub fn set(&mut self, index: u64, value: bool) {
+		assert!(index < self.len, "index {} out of range for length {}",
+			index, self.len);
+		let value = if value {W::ONE} else {W::ZERO};
+		write(self.words, self.left_align, self.start + index, 1, value);
+	}
+
+	/// Narrows the view to the bits in `range`, for reading.
+	///
+	/// Panics if the range does not lie within the view.
+	pub fn slice<R: RangeBounds<u64>>(&self, range: R) -> BitSlice<'_, W> {
+		self.as_slice().slice(range)
+	}
+
+	/// Narrows the view to the bits in `range`.
+	///
+	/// Panics if the range does not lie within the view.
+	pub fn slice_mut<R: RangeBounds<u64>>(&mut self, range: R)
+		-> BitSliceMut<'_, W>
+	{
+		let (start, end) = check_range(range, self.len);
+		BitSliceMut {
+			words: self.words,
+			start: self.start + start,
+			len: end - start,
+			left_align: self.left_align,
+		}
+	}
+
+	pub fn iter(&self) -> Iter<'_, W> {
+		self.as_slice().iter()
+	}
+
+	/// Combines each bit with the bit at the same index of `bits`, a word's
+	/// worth at a time. The two views may differ in alignment and offsets.
+	///
+	/// Panics if the lengths differ.
+	pub fn apply_binary<F>(&mut self, func: F, bits: &BitSlice<'_, W>)
+		where F: Fn(W, W) -> W
+	{
+		assert!(self.len == bits.len, "lengths {} and {} differ",
+			self.len, bits.len);
+		for (index, n) in bits.chunks() {
+			let lhs = self.as_slice().read(index, n);
+			let rhs = bits.read(index, n);
+			let rhs =
+				if self.left_align == bits.left_align {rhs}
+				else {reverse(rhs, n)};
+			write(
+				self.words, self.left_align, self.start + index, n,
+				func(lhs, rhs),
+			);
+		}
+	}
+}
+
+// Reverses the lowest `n` bits, converting a run read in one alignment to
+// the other.
+fn reverse<W: BitStore>(value: W, n: u64) -> W {
+	let mut result = W::ZERO;
+	for i in 0..n {
+		if (value >> i) & W::ONE != W::ZERO {
+			result = result | (W::ONE << (n - 1 - i));
+		}
+	}
+	result
+}
+
+macro_rules! impl_assign_op {
+	($OpAssign:ident, $op_assign:ident, $func:expr) => {
+		impl<W: BitStore> $OpAssign<&BitSlice<'_, W>> for BitSliceMut<'_, W> {
+			fn $op_assign(&mut self, bits: &BitSlice<'_, W>) {
+				self.apply_binary($func, bits)
+			}
+		}
+	};
+}
+
+impl_assign_op!(BitAndAssign, bitand_assign, |x, y| x & y);
+impl_assign_op!(BitOrAssign, bitor_assign, |x, y| x | y);
+impl_assign_op!(BitXorAssign, bitxor_assign, |x, y| x ^ y);
+
+
+/// Iterates over the bits of a `BitSlice` in index order.
+#[derive(Debug, Clone)]
+pub struct Iter<'a, W: BitStore> {
+	bits: BitSlice<'a, W>,
+	front: u64,
+	back: u64,
+}
+
+impl<W: BitStore> Iterator for Iter<'_, W> {
+	type Item = bool;
+
+	fn next(&mut self) -> Option<bool> {
+		if self.front == self.back {
+			return None;
+		}
+		self.front += 1;
+		self.bits.get(self.front - 1)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let len = (self.back - self.front) as usize;
+		(len, Some(len))
+	}
+}
+
+impl<W: BitStore> DoubleEndedIterator for Iter<'_, W> {
+	fn next_back(&mut self) -> Option<bool> {
+		if self.front == self.back {
+			return None;
+		}
+		self.back -= 1;
+		self.bits.get(self.back)
+	}
+}
+
+impl<W: BitStore> ExactSizeIterator for Iter<'_, W> {}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Reads bit `index` of `words` directly, as a reference.
+	fn bit(words: &[u8], left_align: bool, index: u64) -> bool {
+		let (word, offset) = (words[(index / 8) as usize], index % 8);
+		let shift = if left_align {7 - offset} else {offset};
+		(word >> shift) & 1 == 1
+	}
+
+	#[test]
+	fn get() {
+		let words = [0x8000_0000_0000_0001u64, 0b10];
+		let bits = BitSlice::new(&words, true);
+		assert_eq!(bits.length(), 128);
+		assert_eq!(bits.get(0), Some(true));
+		assert_eq!(bits.get(63), Some(true));
+		assert_eq!(bits.get(126), Some(true));
+		assert_eq!(bits.get(127), Some(false));
+		assert_eq!(bits.get(128), None);
+
+		let bits = BitSlice::new(&words, false);
+		assert_eq!(bits.get(0), Some(true));
+		assert_eq!(bits.get(63), Some(true));
+		assert_eq!(bits.get(65), Some(true));
+		assert_eq!(bits.count_ones(), 3);
+
+		let bytes = [0b1000_0001u8, 0xff];
+		for &left_align in &[true, false] {
+			let bits = BitSlice::new(&bytes, left_align);
+			for index in 0..16 {
+				assert_eq!(bits.get(index), Some(bit(&bytes, left_align, index)));
+			}
+		}
+	}
+
+	#[test]
+	fn slice() {
+		let bytes = [0b0011_0101u8, 0b1100_1010, 0xf0];
+		for &left_align in &[true, false] {
+			let bits = BitSlice::new(&bytes, left_align).slice(3..21);
+			assert_eq!((bits.start_offset(), bits.end_offset()), (3, 3));
+			let inner = bits.slice(2..=10);
+			assert_eq!(inner.length(), 9);
+			assert_eq!((inner.start_offset(), inner.end_offset()), (5, 10));
+			for index in 0..9 {
+				assert_eq!(
+					inner.get(index),
+					Some(bit(&bytes, left_align, 5 + index)),
+				);
+			}
+			assert!(bits.slice(18..).is_empty());
+			assert_eq!(
+				bits.count_ones(),
+				(3..21).filter(|&i| bit(&bytes, left_align, i)).count() as u64,
+			);
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "range 4..20 out of range for length 18")]
+	fn slice_out_of_range() {
+		let bytes = [0u8; 3];
+		BitSlice::new(&bytes, true).slice(3..21).slice(4..20);
+	}
+
+	#[test]
+	fn iter() {
+		let bytes = [0b1011_0000u8, 0b0000_0001];
+		let bits = BitSlice::new(&bytes, true).slice(..10);
+		let forward: Vec<_> = bits.iter().collect();
+		assert_eq!(forward, [
+			true, false, true, true, false, false, false, false, false, false,
+		]);
+		let mut backward: Vec<_> = bits.into_iter().rev().collect();
+		backward.reverse();
+		assert_eq!(backward, forward);
+		assert_eq!(bits.iter().len(), 10);
+	}
+
+	#[test]
+	fn set() {
+		let mut words = [0u64; 2];
+		let mut bits = BitSliceMut::new(&mut words, false);
+		bits.set(0, true);
+		bits.set(63, true);
+		bits.set(64, true);
+		bits.slice_mut(100..).set(27, true);
+		assert_eq!(bits.get(127), Some(true));
+		bits.set(0, false);
+		assert_eq!(words, [1 << 63, 1 | 1 << 63]);
+
+		let mut bytes = [0u8; 2];
+		let mut bits = BitSliceMut::new(&mut bytes, true);
+		let mut inner = bits.slice_mut(5..);
+		inner.set(0, true);
+		inner.set(3, true);
+		assert_eq!(inner.iter().filter(|&bit| bit).count(), 2);
+		assert_eq!(bytes, [0b0000_0100, 0b1000_0000]);
+	}
+
+	#[test]
+	#[should_panic(expected = "index 7 out of range for length 7")]
+	fn set_out_of_range() {
+		let mut bytes = [0u8; 2];
+		BitSliceMut::new(&mut bytes, true).slice_mut(9..).set(7, true);
+	}
+
+	#[test]
+	fn bitwise_ops() {
+		let lhs = [0b0110_1100u8, 0b1010_0101, 0b1111_0000, 0b0001_1000];
+		let rhs = [0b1001_0110u8, 0b0011_1100, 0b0101_0101, 0b1110_0111];
+		let ops: [fn(u8, u8) -> u8; 3] = [|x, y| x & y, |x, y| x | y, |x, y| x ^ y];
+
+		for &op in &ops {
+			for &(lhs_align, rhs_align) in &[
+				(true, true), (false, false), (true, false), (false, true),
+			] {
+				for &(lhs_start, rhs_start, len) in &[
+					(0, 0, 32), (3, 5, 20), (7, 1, 9), (1, 7, 25), (4, 4, 0),
+				] {
+					let mut result = lhs;
+					BitSliceMut::new(&mut result, lhs_align)
+						.slice_mut(lhs_start..lhs_start + len)
+						.apply_binary(
+							op,
+							&BitSlice::new(&rhs, rhs_align)
+								.slice(rhs_start..rhs_start + len),
+						);
+
+					for index in 0..32 {
+						let x = bit(&lhs, lhs_align, index);
+						let expected =
+							if index < lhs_start || index >= lhs_start + len {x}
+							else {
+								let y = index - lhs_start + rhs_start;
+								op(x as u8, bit(&rhs, rhs_align, y) as u8) == 1
+							};
+						assert_eq!(bit(&result, lhs_align, index), expected);
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn assign_ops() {
+		let mut words = [0xff00_ff00_ff00_ff00u64, 0];
+		let other = [u64::MAX];
+		let mut bits = BitSliceMut::new(&mut words, true);
+		bits.slice_mut(32..96).bitxor_assign(&BitSlice::new(&other, true));
+		assert_eq!(words, [0xff00_ff00_00ff_00ff, 0xffff_ffff_0000_0000]);
+
+		let mut bits = BitSliceMut::new(&mut words, true);
+		let mut inner = bits.slice_mut(64..);
+		inner &= &BitSlice::new(&other, false);
+		inner |= &BitSlice::new(&[1u64], false);
+		assert_eq!(words[1], 0x8000_0000_0000_0000 | 0xffff_ffff_0000_0000);
+	}
+
+	#[test]
+	#[should_panic(expected = "lengths 8 and 9 differ")]
+	fn length_mismatch() {
+		let (mut lhs, rhs) = ([0u8; 2], [0u8; 2]);
+		BitSliceMut::new(&mut lhs, true).slice_mut(..8)
+			.apply_binary(|x, y| x | y, &BitSlice::new(&rhs, true).slice(..9));
+	}
+}
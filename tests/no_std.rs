@@ -0,0 +1,57 @@
+//! Checks that the library builds without `std`. The host check builds it
+//! as `no_std` for the host, so any stray use of `std` fails there too. The
+//! bare-metal check goes further, with no `std` available at all, but needs
+//! the target; it is ignored by default, so add the target with
+//! `rustup target add thumbv7em-none-eabi` and run
+//! `cargo test --test no_std -- --ignored`.
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+const TARGET: &str = "thumbv7em-none-eabi";
+const FEATURES: &[&str] = &["", "alloc"];
+
+fn target_installed() -> bool {
+	let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+	let sysroot = match Command::new(rustc).args(["--print", "sysroot"]).output() {
+		Ok(output) => String::from_utf8(output.stdout).unwrap(),
+		Err(_) => return false,
+	};
+	Path::new(sysroot.trim()).join("lib/rustlib").join(TARGET).exists()
+}
+
+// Checks the library with only `features` enabled, for `target` or else the
+// host, in a target directory of its own.
+fn check(features: &str, target: Option<&str>) -> bool {
+	let manifest_dir = env!("CARGO_MANIFEST_DIR");
+	let mut command = Command::new(env!("CARGO"));
+	command.args(["check", "--lib", "--no-default-features"])
+		.args(["--features", features])
+		.args(["--target-dir", &format!("{}/target/no_std", manifest_dir)])
+		.current_dir(manifest_dir);
+	if let Some(target) = target {
+		command.args(["--target", target]);
+	}
+	command.status().unwrap().success()
+}
+
+#[test]
+fn check_host() {
+	for features in FEATURES {
+		assert!(check(features, None),
+			"features {:?} do not build without std", features);
+	}
+}
+
+#[test]
+#[ignore = "needs the thumbv7em-none-eabi target"]
+fn check_bare_metal() {
+	assert!(target_installed(), "the {} target is not installed; add it \
+		with `rustup target add {}`", TARGET, TARGET);
+
+	for features in FEATURES {
+		assert!(check(features, Some(TARGET)),
+			"features {:?} do not build on {}", features, TARGET);
+	}
+}
@@ -0,0 +1,118 @@
+//! Algebraic laws checked across random words, margins and alignments,
+//! including the empty and full-width windows.
+
+use std::collections::hash_map::DefaultHasher;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+
+use bitarray::bitarray::BitArray;
+use bitarray::bitarrayn::BitArrayN;
+use bitarray::bytes::{BitOrder, ByteOrder};
+use proptest::prelude::*;
+
+fn bit_array() -> impl Strategy<Value = BitArray<u64>> {
+	(0..=64u64)
+		.prop_flat_map(|left_margin| (
+			any::<u64>(),
+			Just(left_margin),
+			0..=64 - left_margin,
+			any::<bool>(),
+		))
+		.prop_map(|(word, left_margin, right_margin, left_align)| {
+			let mask = BitArray::<u64>::from_margins(
+				0, left_margin, right_margin, left_align,
+			).unwrap().mask();
+			BitArray::<u64>::from_margins(
+				word & mask, left_margin, right_margin, left_align,
+			).unwrap()
+		})
+}
+
+fn hash_of<T: Hash>(value: &T) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	value.hash(&mut hasher);
+	hasher.finish()
+}
+
+proptest! {
+	#[test]
+	fn xor_is_self_inverse(a in bit_array(), b in bit_array()) {
+		prop_assert!((a ^ a).none());
+		prop_assert_eq!((a ^ a).length(), a.length());
+		prop_assert_eq!((a ^ b) ^ b, a.trim_to(b.length()));
+	}
+
+	#[test]
+	fn de_morgan(a in bit_array(), b in bit_array()) {
+		prop_assert_eq!(!(a & b), !a | !b);
+		prop_assert_eq!(!(a | b), !a & !b);
+		prop_assert_eq!(!!a, a);
+	}
+
+	#[test]
+	fn trim_to_is_idempotent(a in bit_array(), len in 0..=64u64) {
+		let trimmed = a.trim_to(len);
+		prop_assert_eq!(trimmed.length(), u64::min(len, a.length()));
+		prop_assert_eq!(trimmed.trim_to(len), trimmed);
+	}
+
+	#[test]
+	fn aligned_to_preserves_value(a in bit_array(), b in bit_array()) {
+		let margin =
+			if b.is_left_aligned() {b.left_margin()}
+			else {b.right_margin()};
+		prop_assume!(margin + a.length() <= 64);
+
+		let aligned = a.aligned_to(b);
+		prop_assert_eq!(u64::from(aligned), u64::from(a));
+		prop_assert_eq!(aligned, a);
+	}
+
+	#[test]
+	fn equal_arrays_hash_equally(a in bit_array(), b in bit_array()) {
+		let room =
+			if b.is_left_aligned() {64 - b.left_margin()}
+			else {64 - b.right_margin()};
+		let moved = a.trim_to(room).aligned_to(b);
+		prop_assert_eq!(moved, a.trim_to(room));
+		prop_assert_eq!(hash_of(&moved), hash_of(&a.trim_to(room)));
+		if a == b {
+			prop_assert_eq!(hash_of(&a), hash_of(&b));
+		}
+	}
+
+	#[test]
+	fn ord_agrees_with_eq(a in bit_array(), b in bit_array()) {
+		prop_assert_eq!(a.cmp(&b) == std::cmp::Ordering::Equal, a == b);
+		prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+		prop_assert!(a >= a.trim_to(1));
+	}
+
+	#[test]
+	fn text_and_bytes_roundtrip(a in bit_array()) {
+		let suffix = if a.is_left_aligned() {":L"} else {":R"};
+		let literal = format!("{}{}", a, suffix);
+		prop_assert_eq!(literal.parse::<BitArray<u64>>(), Ok(a));
+
+		for &order in &[ByteOrder::Big, ByteOrder::Little] {
+			for &bit_order in &[BitOrder::Msb0, BitOrder::Lsb0] {
+				let bytes = a.to_bytes(order, bit_order);
+				prop_assert_eq!(
+					BitArray::from_bytes(
+						&bytes, a.length(), order, bit_order, a.is_left_aligned(),
+					),
+					Ok(a),
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn multi_word_ops_match_single_word(a in bit_array(), b in bit_array()) {
+		let wide = |bits| BitArrayN::<3>::try_from(bits).unwrap();
+		let narrow = |bits| BitArray::try_from(bits).unwrap();
+		prop_assert_eq!(narrow(wide(a) ^ wide(b)), a ^ b);
+		prop_assert_eq!(narrow(!wide(a) & wide(b)), !a & b);
+		prop_assert_eq!(narrow(wide(a).trim_to(b.length())), a.trim_to(b.length()));
+	}
+}